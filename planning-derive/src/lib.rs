@@ -0,0 +1,327 @@
+//! # Planning Derive
+//!
+//! Derive macros for the `Action` and `Goal` traits of the `planning` crate,
+//! which are re-exported by it with the `derive` feature.
+//!
+//! Each variant of an enum is annotated with the expressions that would otherwise be written in a `match self`.
+//! The expressions can use `state`, and the fields of their variant by name,
+//! or as `_0`, `_1` and so on for tuple variants.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use std::collections::HashMap;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Expr, Fields, Result, Type, Variant};
+
+/// Derives `Action<S, C>` for an enum.
+///
+/// The enum is annotated with `#[action(state = S)]`, and optionally `cost = C` if the cost type isn't `i32`.
+/// Each variant is annotated with:
+/// - `effect`, which modifies `state` when the action is applied;
+/// - `requires`, which is true if the action is applicable to `state`, and is always true if not given;
+/// - `cost`, which is the cost of applying the action to `state`, and is one if not given.
+///
+/// # Example
+/// ```
+/// use planning::*;
+///
+/// #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// struct State {
+///     num_flowers: u16,
+///     picnic_set: bool,
+/// }
+///
+/// #[derive(Action, Clone, Debug, PartialEq, Eq, Hash)]
+/// #[action(state = State)]
+/// enum MyAction {
+///     #[action(requires = state.num_flowers < 5, effect = state.num_flowers += *count, cost = *count as i32)]
+///     PickFlowers { count: u16 },
+///     #[action(requires = !state.picnic_set, effect = state.picnic_set = true, cost = 3)]
+///     SetPicnic,
+/// }
+///
+/// let state = State { num_flowers: 0, picnic_set: false };
+/// let action = MyAction::PickFlowers { count: 2 };
+/// assert!(action.is_applicable(&state));
+/// assert_eq!(action.apply(&state).num_flowers, 2);
+/// assert_eq!(action.cost(&state), 2);
+/// assert_eq!(MyAction::SetPicnic.cost(&state), 3);
+/// ```
+///
+/// Every variant needs an effect:
+/// ```compile_fail
+/// use planning::*;
+///
+/// #[derive(Action, Clone, Debug, PartialEq, Eq, Hash)]
+/// #[action(state = bool)]
+/// enum MyAction {
+///     #[action(requires = !*state)]
+///     Toggle,
+/// }
+/// ```
+#[proc_macro_derive(Action, attributes(action))]
+pub fn derive_action(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    action(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Derives `Goal<S, C>` for an enum.
+///
+/// The enum is annotated with `#[goal(state = S)]`, and optionally `cost = C` if the cost type isn't `i32`.
+/// Each variant is annotated with:
+/// - `satisfied`, which is true if the goal is satisfied in `state`;
+/// - `priority`, which is the priority of the goal in `state`, and is zero if not given;
+/// - `heuristic`, which estimates the cost to satisfy the goal from `state`, and is zero if not given.
+///
+/// # Example
+/// ```
+/// use planning::*;
+///
+/// #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// struct State {
+///     num_flowers: u16,
+///     hungry: bool,
+/// }
+///
+/// #[derive(Goal, Clone, Debug, PartialEq, Eq, Hash)]
+/// #[goal(state = State)]
+/// enum MyGoal {
+///     #[goal(
+///         satisfied = state.num_flowers >= *_0,
+///         priority = 1,
+///         heuristic = (*_0 as i32 - state.num_flowers as i32).max(0),
+///     )]
+///     Bouquet(u16),
+///     #[goal(satisfied = !state.hungry, priority = if state.hungry { 2 } else { 0 })]
+///     Eaten,
+/// }
+///
+/// let state = State { num_flowers: 1, hungry: true };
+/// assert!(!MyGoal::Bouquet(5).is_satisfied(&state));
+/// assert_eq!(MyGoal::Bouquet(5).heuristic(&state), 4);
+/// assert_eq!(MyGoal::Eaten.priority(&state), 2);
+/// ```
+#[proc_macro_derive(Goal, attributes(goal))]
+pub fn derive_goal(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    goal(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn action(input: &DeriveInput) -> Result<TokenStream2> {
+    let (state, cost) = container(input, "action")?;
+    let variants = variants(
+        input,
+        "action",
+        &["requires", "effect", "cost"],
+        &["effect"],
+    )?;
+
+    let patterns: Vec<_> = variants.iter().map(|(pattern, _)| pattern).collect();
+    let requires = variants.iter().map(|(_, options)| {
+        options
+            .get("requires")
+            .map_or(quote!(true), |expr| quote!(#expr))
+    });
+    let effects = variants.iter().map(|(_, options)| &options["effect"]);
+    let costs = variants.iter().map(|(_, options)| {
+        options.get("cost").map_or(
+            quote!(<#cost as ::planning::Cost>::ONE),
+            |expr| quote!(#expr),
+        )
+    });
+
+    let ident = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::planning::Action<#state, #cost> for #ident #type_generics #where_clause {
+            #[allow(unused_variables)]
+            fn is_applicable(&self, state: &#state) -> bool {
+                match self {
+                    #(#patterns => #requires,)*
+                }
+            }
+
+            #[allow(unused_variables)]
+            fn apply_mut(&self, state: &mut #state) {
+                match self {
+                    #(#patterns => {
+                        #effects;
+                    })*
+                }
+            }
+
+            #[allow(unused_variables)]
+            fn cost(&self, state: &#state) -> #cost {
+                match self {
+                    #(#patterns => #costs,)*
+                }
+            }
+        }
+    })
+}
+
+fn goal(input: &DeriveInput) -> Result<TokenStream2> {
+    let (state, cost) = container(input, "goal")?;
+    let variants = variants(
+        input,
+        "goal",
+        &["satisfied", "priority", "heuristic"],
+        &["satisfied"],
+    )?;
+
+    let patterns: Vec<_> = variants.iter().map(|(pattern, _)| pattern).collect();
+    let satisfied = variants.iter().map(|(_, options)| &options["satisfied"]);
+    let or_zero = |key: &str| -> Vec<TokenStream2> {
+        variants
+            .iter()
+            .map(|(_, options)| {
+                options.get(key).map_or(
+                    quote!(<#cost as ::planning::Cost>::ZERO),
+                    |expr| quote!(#expr),
+                )
+            })
+            .collect()
+    };
+    let priorities = or_zero("priority");
+    let heuristics = or_zero("heuristic");
+
+    let ident = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::planning::Goal<#state, #cost> for #ident #type_generics #where_clause {
+            #[allow(unused_variables)]
+            fn is_satisfied(&self, state: &#state) -> bool {
+                match self {
+                    #(#patterns => #satisfied,)*
+                }
+            }
+
+            #[allow(unused_variables)]
+            fn heuristic(&self, state: &#state) -> #cost {
+                match self {
+                    #(#patterns => #heuristics,)*
+                }
+            }
+
+            #[allow(unused_variables)]
+            fn priority(&self, state: &#state) -> #cost {
+                match self {
+                    #(#patterns => #priorities,)*
+                }
+            }
+        }
+    })
+}
+
+/// Reads the state and cost types from the attribute on the enum.
+fn container(input: &DeriveInput, name: &str) -> Result<(Type, Type)> {
+    let mut state = None;
+    let mut cost = None;
+    for attribute in input
+        .attrs
+        .iter()
+        .filter(|attribute| attribute.path().is_ident(name))
+    {
+        attribute.parse_nested_meta(|meta| {
+            if meta.path.is_ident("state") {
+                state = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("cost") {
+                cost = Some(meta.value()?.parse()?);
+            } else {
+                return Err(meta.error("expected `state` or `cost`"));
+            }
+            Ok(())
+        })?;
+    }
+    let Some(state) = state else {
+        let message = format!("missing `#[{name}(state = ...)]` attribute naming the state type");
+        return Err(Error::new(input.ident.span(), message));
+    };
+    Ok((state, cost.unwrap_or_else(|| syn::parse_quote!(i32))))
+}
+
+/// Reads the expressions given for each variant, returning them with a pattern matching the variant.
+fn variants(
+    input: &DeriveInput,
+    name: &str,
+    keys: &[&str],
+    required: &[&str],
+) -> Result<Vec<(TokenStream2, HashMap<String, Expr>)>> {
+    let Data::Enum(data) = &input.data else {
+        let message = format!("`{}` can only be derived for enums", capitalize(name));
+        return Err(Error::new(input.ident.span(), message));
+    };
+    if data.variants.is_empty() {
+        let message = format!(
+            "`{}` can't be derived for an enum without variants",
+            capitalize(name)
+        );
+        return Err(Error::new(input.ident.span(), message));
+    }
+    data.variants
+        .iter()
+        .map(|variant| {
+            let options = options(variant, name, keys)?;
+            for key in required {
+                if !options.contains_key(*key) {
+                    let message = format!(
+                        "missing `{key}` for variant `{}`, add `#[{name}({key} = ...)]`",
+                        variant.ident
+                    );
+                    return Err(Error::new(variant.ident.span(), message));
+                }
+            }
+            Ok((pattern(variant), options))
+        })
+        .collect()
+}
+
+fn options(variant: &Variant, name: &str, keys: &[&str]) -> Result<HashMap<String, Expr>> {
+    let mut options = HashMap::new();
+    for attribute in variant
+        .attrs
+        .iter()
+        .filter(|attribute| attribute.path().is_ident(name))
+    {
+        attribute.parse_nested_meta(|meta| {
+            let Some(key) = keys.iter().find(|key| meta.path.is_ident(key)) else {
+                let expected: Vec<_> = keys.iter().map(|key| format!("`{key}`")).collect();
+                return Err(meta.error(format!("expected one of {}", expected.join(", "))));
+            };
+            if options.contains_key(*key) {
+                return Err(meta.error(format!("`{key}` is given more than once")));
+            }
+            options.insert(key.to_string(), meta.value()?.parse()?);
+            Ok(())
+        })?;
+    }
+    Ok(options)
+}
+
+/// Returns a pattern binding the fields of the variant by name, or as `_0`, `_1`, ... for tuple variants.
+fn pattern(variant: &Variant) -> TokenStream2 {
+    let ident = &variant.ident;
+    match &variant.fields {
+        Fields::Named(fields) => {
+            let names = fields.named.iter().map(|field| &field.ident);
+            quote!(Self::#ident { #(#names),* })
+        }
+        Fields::Unnamed(fields) => {
+            let names = (0..fields.unnamed.len()).map(|index| format_ident!("_{}", index));
+            quote!(Self::#ident(#(#names),*))
+        }
+        Fields::Unit => quote!(Self::#ident),
+    }
+}
+
+fn capitalize(name: &str) -> String {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
@@ -1,3 +1,4 @@
+use crate::Cost;
 use std::hash::Hash;
 
 /// Defines a state transition with pre-conditions and an optional cost.
@@ -14,6 +15,9 @@ use std::hash::Hash;
 /// This method is useful when actions are not equally difficult, such as waiting or pathfinding.
 /// When choosing a plan, the algorithm will choose the sequence with the lowest total cost.
 ///
+/// Costs are `i32` by default. Any other type implementing `Cost` can be used by implementing `Action<S, C>`,
+/// as long as the goals being planned for use the same type.
+///
 /// # Example
 /// ```
 /// # use planning::*;
@@ -43,9 +47,10 @@ use std::hash::Hash;
 /// MakeCorrect.apply_mut(&mut state);
 /// assert_eq!(state, State { is_correct: true });
 ///```
-pub trait Action<S>
+pub trait Action<S, C = i32>
 where
     S: Clone + Hash + Eq,
+    C: Cost,
 {
     /// Returns true if the action can be applied to the given state.
     fn is_applicable(&self, state: &S) -> bool;
@@ -69,11 +74,90 @@ where
     /// Implementing this method is optional, and it will default to a constant value if not implemented.
     /// This method is useful for more complex plans which include actions like pathfinding, waiting, etc.
     /// When choosing a plan, the algorithm will choose the path with the lowest total cost.
-    fn cost(&self, _state: &S) -> i32 {
-        1
+    fn cost(&self, _state: &S) -> C {
+        C::ONE
     }
 }
 
+/// The progress of an action which takes time to perform.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ActionStatus {
+    /// The action is still being performed.
+    Running,
+    /// The action finished successfully.
+    Succeeded,
+    /// The action could not be finished.
+    Failed,
+}
+
+/// Extends an action so that it can be performed in a world over multiple ticks.
+///
+/// Planning treats actions as instantaneous, but performing them may take time and fail part way.
+/// `Executor::update` starts the action of each step, ticks it until it succeeds or fails,
+/// and replans from the agent's current state if it fails.
+///
+/// The world `W` is whatever the action acts upon, such as a game world or a simulation.
+/// Each method also receives the agent's state, which it should update with what it observes,
+/// since the executor checks the next step against it.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+///
+/// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+/// struct State {
+///     at_tree: bool,
+/// }
+///
+/// struct World {
+///     distance_to_tree: u32,
+/// }
+///
+/// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+/// struct WalkToTree;
+///
+/// impl Action<State> for WalkToTree {
+///     fn is_applicable(&self, state: &State) -> bool {
+///         !state.at_tree
+///     }
+///
+///     fn apply_mut(&self, state: &mut State) {
+///         state.at_tree = true;
+///     }
+/// }
+///
+/// impl DurativeAction<State, World> for WalkToTree {
+///     fn tick(&self, state: &mut State, world: &mut World) -> ActionStatus {
+///         world.distance_to_tree -= 1;
+///         state.at_tree = world.distance_to_tree == 0;
+///         match state.at_tree {
+///             true => ActionStatus::Succeeded,
+///             false => ActionStatus::Running,
+///         }
+///     }
+/// }
+///
+/// let mut world = World { distance_to_tree: 2 };
+/// let mut state = State { at_tree: false };
+/// assert_eq!(WalkToTree.tick(&mut state, &mut world), ActionStatus::Running);
+/// assert_eq!(WalkToTree.tick(&mut state, &mut world), ActionStatus::Succeeded);
+/// assert_eq!(state, State { at_tree: true });
+/// ```
+pub trait DurativeAction<S, W, C = i32>: Action<S, C>
+where
+    S: Clone + Hash + Eq,
+    C: Cost,
+{
+    /// Called once when the action starts being performed.
+    fn start(&self, _state: &mut S, _world: &mut W) {}
+
+    /// Performs the action for one tick, and returns whether it is finished.
+    fn tick(&self, state: &mut S, world: &mut W) -> ActionStatus;
+
+    /// Called if the action is interrupted before it finished, such as when its plan is invalidated.
+    fn abort(&self, _state: &mut S, _world: &mut W) {}
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
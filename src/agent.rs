@@ -1,6 +1,16 @@
-use crate::{plan, Action, Goal};
+use crate::{
+    check_heuristic, plan, plan_counted, try_plan_counted, Action, BudgetExhausted, Cost, Goal,
+    HeuristicReport, Plan, PlanError, PlanResult, SearchBudget,
+};
+use std::cmp::Reverse;
 use std::hash::Hash;
 
+/// A goal with the actions of a plan satisfying it and their total cost.
+type GoalActions<'a, G, A, C> = (&'a G, Vec<A>, C);
+
+/// The goal planned for with its plan, or the reason no goal could be planned for.
+type GoalPlan<'a, S, A, G, C> = Result<(&'a G, Plan<S, A, C>), PlanError<S>>;
+
 /// A stateful agent capable of choosing from multiple goals based on priority.
 ///
 /// Given a current state, a list of possible actions, and a list of goals,
@@ -82,12 +92,7 @@ use std::hash::Hash;
 #[cfg_attr(feature = "bevy", derive(bevy::prelude::Component))]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct Agent<S, A, G>
-where
-    S: Clone + Hash + Eq,
-    A: Action<S> + Eq + Clone + Hash,
-    G: Goal<S> + Clone,
-{
+pub struct Agent<S, A, G> {
     pub state: S,
     pub actions: Vec<A>,
     pub goals: Vec<G>,
@@ -96,13 +101,17 @@ where
 impl<S, A, G> Agent<S, A, G>
 where
     S: Clone + Hash + Eq,
-    A: Action<S> + Eq + Clone + Hash,
-    G: Goal<S> + Clone,
+    A: Eq + Clone + Hash,
+    G: Clone,
 {
     /// Creates a new agent with the given initial state, possible actions, and goals.
     ///
     /// On initialization, the goals are sorted in descending order by priority, based on the given state.
-    pub fn new(state: S, actions: Vec<A>, goals: Vec<G>) -> Self {
+    pub fn new<C: Cost>(state: S, actions: Vec<A>, goals: Vec<G>) -> Self
+    where
+        A: Action<S, C>,
+        G: Goal<S, C>,
+    {
         let mut new = Self {
             state,
             actions,
@@ -113,21 +122,56 @@ where
     }
 
     // Sort in descending order of priority
-    fn sort_goals(&mut self) {
+    pub(crate) fn sort_goals<C: Cost>(&mut self)
+    where
+        A: Action<S, C>,
+        G: Goal<S, C>,
+    {
         self.goals
-            .sort_by(|a, b| b.priority(&self.state).cmp(&a.priority(&self.state)));
+            .sort_by_key(|goal| Reverse(goal.priority(&self.state)));
     }
 
     /// Returns the plan and total cost for the first goal that can be satisfied.
     ///
     /// This method **does not** sort the goals by priority before searching.
     ///**If your goals return dynamic priorities based on the current state, use `plan_dynamic` instead.**
-    pub fn plan_constant(&self) -> Option<(&G, Vec<A>, i32)> {
+    pub fn plan_constant<C: Cost>(&self) -> Option<GoalActions<'_, G, A, C>>
+    where
+        A: Action<S, C>,
+        G: Goal<S, C>,
+    {
         self.goals.iter().find_map(|goal| {
             plan(&self.state, &self.actions, goal).map(|(path, cost)| (goal, path, cost))
         })
     }
 
+    /// Returns the plan and total cost for the first goal that can be satisfied, searching within the given budget.
+    ///
+    /// The budget is shared between the searches for all goals.
+    /// If it runs out before a plan is found, `Err(BudgetExhausted)` is returned,
+    /// as a higher priority goal may still have been achievable.
+    ///
+    /// This method **does not** sort the goals by priority before searching.
+    pub fn plan_constant_with_budget<C: Cost>(
+        &self,
+        budget: &SearchBudget<C>,
+    ) -> Result<Option<GoalActions<'_, G, A, C>>, BudgetExhausted>
+    where
+        A: Action<S, C>,
+        G: Goal<S, C>,
+    {
+        let mut expanded = 0;
+        for goal in &self.goals {
+            let (result, count) =
+                plan_counted(&self.state, &self.actions, goal, &budget.after(expanded));
+            expanded += count;
+            if let Some((path, cost)) = result? {
+                return Ok(Some((goal, path, cost)));
+            }
+        }
+        Ok(None)
+    }
+
     /// Returns the plan and total cost for the first goal that can be satisfied.
     ///
     /// This method sorts the goals by priority based on the current state before searching.
@@ -199,15 +243,80 @@ where
     /// let (goal, _, _) = agent.plan_dynamic().unwrap();
     /// assert_eq!(goal, &MyGoal::Eaten);
     /// ```
-    pub fn plan_dynamic(&mut self) -> Option<(&G, Vec<A>, i32)> {
+    pub fn plan_dynamic<C: Cost>(&mut self) -> Option<GoalActions<'_, G, A, C>>
+    where
+        A: Action<S, C>,
+        G: Goal<S, C>,
+    {
         self.sort_goals();
         self.plan_constant()
     }
 
+    /// Returns the plan and total cost for the first goal that can be satisfied, searching within the given budget.
+    ///
+    /// This method sorts the goals by priority based on the current state before searching,
+    /// and shares the budget between goals like `plan_constant_with_budget`.
+    ///
+    /// # Example
+    /// ```
+    /// # use planning::*;
+    ///
+    /// #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    /// struct State {
+    ///     distance: u32,
+    /// }
+    ///
+    /// #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    /// struct Walk;
+    ///
+    /// impl Action<State> for Walk {
+    ///     fn is_applicable(&self, _state: &State) -> bool {
+    ///         true
+    ///     }
+    ///
+    ///     fn apply_mut(&self, state: &mut State) {
+    ///         state.distance += 1;
+    ///     }
+    /// }
+    ///
+    /// #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    /// struct Arrive(u32);
+    ///
+    /// impl Goal<State> for Arrive {
+    ///     fn is_satisfied(&self, state: &State) -> bool {
+    ///         state.distance == self.0
+    ///     }
+    /// }
+    ///
+    /// let mut agent = Agent::new(State { distance: 0 }, vec![Walk], vec![Arrive(100)]);
+    ///
+    /// let budget = SearchBudget::unlimited().with_max_expanded(10);
+    /// assert_eq!(agent.plan_dynamic_with_budget(&budget), Err(BudgetExhausted));
+    ///
+    /// let budget = SearchBudget::unlimited().with_max_expanded(100);
+    /// let (_, plan, _) = agent.plan_dynamic_with_budget(&budget).unwrap().unwrap();
+    /// assert_eq!(plan.len(), 100);
+    /// ```
+    pub fn plan_dynamic_with_budget<C: Cost>(
+        &mut self,
+        budget: &SearchBudget<C>,
+    ) -> Result<Option<GoalActions<'_, G, A, C>>, BudgetExhausted>
+    where
+        A: Action<S, C>,
+        G: Goal<S, C>,
+    {
+        self.sort_goals();
+        self.plan_constant_with_budget(budget)
+    }
+
     /// Calculates the best plan for each of the agent's goals and returns all possible plans.
     ///
     /// Returned plans are in arbitrary order.
-    pub fn plan_all(&self) -> Vec<(&G, Vec<A>, i32)> {
+    pub fn plan_all<C: Cost>(&self) -> Vec<GoalActions<'_, G, A, C>>
+    where
+        A: Action<S, C>,
+        G: Goal<S, C>,
+    {
         self.goals
             .iter()
             .filter_map(|goal| {
@@ -216,6 +325,33 @@ where
             .collect()
     }
 
+    /// Calculates the best plan for each of the agent's goals within the given budget, and returns all possible plans.
+    ///
+    /// The budget is shared between the searches for all goals.
+    /// If it runs out before every goal was searched, `Err(BudgetExhausted)` is returned.
+    ///
+    /// Returned plans are in arbitrary order.
+    pub fn plan_all_with_budget<C: Cost>(
+        &self,
+        budget: &SearchBudget<C>,
+    ) -> Result<Vec<GoalActions<'_, G, A, C>>, BudgetExhausted>
+    where
+        A: Action<S, C>,
+        G: Goal<S, C>,
+    {
+        let mut expanded = 0;
+        let mut plans = Vec::new();
+        for goal in &self.goals {
+            let (result, count) =
+                plan_counted(&self.state, &self.actions, goal, &budget.after(expanded));
+            expanded += count;
+            if let Some((path, cost)) = result? {
+                plans.push((goal, path, cost));
+            }
+        }
+        Ok(plans)
+    }
+
     /// Calculates all possible goals and returns the plan with the highest profit.
     ///
     /// Profit is defined as the difference between the goal's priority and the total cost of the plan.
@@ -288,9 +424,223 @@ where
     /// assert_eq!(plan, vec![Sell::SellBanana]);
     /// assert_eq!(goal, &Sell::SellBanana); // More profitable
     /// ```
-    pub fn plan_profit(&self) -> Option<(&G, Vec<A>, i32)> {
+    pub fn plan_profit<C: Cost>(&self) -> Option<GoalActions<'_, G, A, C>>
+    where
+        A: Action<S, C>,
+        G: Goal<S, C>,
+    {
         self.plan_all()
             .into_iter()
-            .max_by_key(|(goal, _, cost)| goal.priority(&self.state) - cost)
+            .max_by(|(a, _, a_cost), (b, _, b_cost)| {
+                let a_priority = a.priority(&self.state);
+                let b_priority = b.priority(&self.state);
+                // Compare priority - cost without subtracting, so unsigned costs can't underflow
+                (a_priority + *b_cost).cmp(&(b_priority + *a_cost))
+            })
+    }
+
+    /// Returns the plan for the first goal that can be satisfied, or the reason none was found.
+    ///
+    /// Goals which are already satisfied are skipped, as there is nothing to plan for them.
+    /// The budget is shared between the searches for all goals, and the search stops as soon as it runs out.
+    ///
+    /// If no goal could be planned for, the error of the first goal which failed is returned.
+    /// `PlanError::AlreadySatisfied` is only returned if every goal is satisfied.
+    ///
+    /// This method **does not** sort the goals by priority before searching.
+    ///**If your goals return dynamic priorities based on the current state, use `try_plan_dynamic` instead.**
+    pub fn try_plan_constant<C: Cost>(&self, budget: &SearchBudget<C>) -> GoalPlan<'_, S, A, G, C>
+    where
+        A: Action<S, C>,
+        G: Goal<S, C>,
+    {
+        let mut expanded = 0;
+        let mut errors = Vec::new();
+        for goal in &self.goals {
+            let (result, count) =
+                try_plan_counted(&self.state, &self.actions, goal, &budget.after(expanded));
+            expanded += count;
+            match result {
+                Ok(plan) => return Ok((goal, plan)),
+                Err(error @ PlanError::BudgetExhausted(_)) => return Err(error),
+                Err(error) => errors.push(error),
+            }
+        }
+        Err(first_error(errors))
+    }
+
+    /// Returns the plan for the first goal that can be satisfied, or the reason none was found.
+    ///
+    /// This method sorts the goals by priority based on the current state before searching,
+    /// and otherwise behaves like `try_plan_constant`.
+    ///
+    /// # Example
+    /// ```
+    /// # use planning::*;
+    ///
+    /// #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    /// struct State {
+    ///     has_worked: bool,
+    ///     hungry: bool,
+    /// }
+    ///
+    /// #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    /// struct Work;
+    ///
+    /// impl Action<State> for Work {
+    ///     fn is_applicable(&self, state: &State) -> bool {
+    ///         !state.has_worked
+    ///     }
+    ///
+    ///     fn apply_mut(&self, state: &mut State) {
+    ///         state.has_worked = true;
+    ///     }
+    /// }
+    ///
+    /// #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    /// enum MyGoal {
+    ///     Worked,
+    ///     Eaten,
+    /// }
+    ///
+    /// impl Goal<State> for MyGoal {
+    ///     fn is_satisfied(&self, state: &State) -> bool {
+    ///         match self {
+    ///             MyGoal::Worked => state.has_worked,
+    ///             MyGoal::Eaten => !state.hungry,
+    ///         }
+    ///     }
+    ///
+    ///     fn priority(&self, state: &State) -> i32 {
+    ///         match self {
+    ///             MyGoal::Worked => 1,
+    ///             MyGoal::Eaten => 2,
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let mut agent = Agent::new(
+    ///     State { has_worked: false, hungry: false },
+    ///     vec![Work],
+    ///     vec![MyGoal::Worked, MyGoal::Eaten],
+    /// );
+    /// let budget = SearchBudget::unlimited();
+    ///
+    /// // Eating is already done, so the agent works instead
+    /// let (goal, plan) = agent.try_plan_dynamic(&budget).unwrap();
+    /// assert_eq!(goal, &MyGoal::Worked);
+    /// assert_eq!(plan.into_actions(), vec![Work]);
+    ///
+    /// agent.state.has_worked = true;
+    /// let result = agent.try_plan_dynamic(&budget);
+    /// assert_eq!(result, Err(PlanError::AlreadySatisfied));
+    ///
+    /// // There is no action to satisfy hunger
+    /// agent.state.hungry = true;
+    /// let result = agent.try_plan_dynamic(&budget);
+    /// assert_eq!(result, Err(PlanError::NoApplicableActions));
+    /// ```
+    pub fn try_plan_dynamic<C: Cost>(
+        &mut self,
+        budget: &SearchBudget<C>,
+    ) -> GoalPlan<'_, S, A, G, C>
+    where
+        A: Action<S, C>,
+        G: Goal<S, C>,
+    {
+        self.sort_goals();
+        self.try_plan_constant(budget)
+    }
+
+    /// Calculates the best plan for each of the agent's goals, or the reason none was found.
+    ///
+    /// The budget is shared between the searches for all goals.
+    /// Once it runs out, the remaining goals report `PlanError::BudgetExhausted`.
+    ///
+    /// Results are returned in the same order as the agent's goals.
+    pub fn try_plan_all<C: Cost>(&self, budget: &SearchBudget<C>) -> Vec<(&G, PlanResult<S, A, C>)>
+    where
+        A: Action<S, C>,
+        G: Goal<S, C>,
+    {
+        let mut expanded = 0;
+        self.goals
+            .iter()
+            .map(|goal| {
+                let (result, count) =
+                    try_plan_counted(&self.state, &self.actions, goal, &budget.after(expanded));
+                expanded += count;
+                (goal, result)
+            })
+            .collect()
+    }
+
+    /// Calculates all possible goals and returns the plan with the highest profit, or the reason none was found.
+    ///
+    /// Profit is defined as in `plan_profit`. If no goal could be planned for,
+    /// errors are reported as in `try_plan_constant`, with budget exhaustion taking precedence.
+    pub fn try_plan_profit<C: Cost>(&self, budget: &SearchBudget<C>) -> GoalPlan<'_, S, A, G, C>
+    where
+        A: Action<S, C>,
+        G: Goal<S, C>,
+    {
+        let mut errors = Vec::new();
+        let best = self
+            .try_plan_all(budget)
+            .into_iter()
+            .filter_map(|(goal, result)| match result {
+                Ok(plan) => Some((goal, plan)),
+                Err(error) => {
+                    errors.push(error);
+                    None
+                }
+            })
+            .max_by(|(a, a_plan), (b, b_plan)| {
+                let a_priority = a.priority(&self.state);
+                let b_priority = b.priority(&self.state);
+                (a_priority + b_plan.cost()).cmp(&(b_priority + a_plan.cost()))
+            });
+        best.ok_or_else(|| first_error(errors))
+    }
+
+    /// Checks the heuristic of each of the agent's goals from the agent's current state.
+    ///
+    /// Each goal's search explores at most `max_states` states. See `check_heuristic` for details.
+    ///
+    /// Results are returned in the same order as the agent's goals.
+    pub fn check_heuristics<C: Cost>(
+        &self,
+        max_states: usize,
+    ) -> Vec<(&G, HeuristicReport<S, A, C>)>
+    where
+        A: Action<S, C>,
+        G: Goal<S, C>,
+    {
+        self.goals
+            .iter()
+            .map(|goal| {
+                let report = check_heuristic(&self.state, &self.actions, goal, max_states);
+                (goal, report)
+            })
+            .collect()
+    }
+}
+
+/// Picks the most relevant error out of the errors for each goal, in priority order.
+///
+/// Budget exhaustion takes precedence, then the first goal which could not be planned for.
+/// If every goal was already satisfied, or there are no goals, `AlreadySatisfied` is returned.
+pub(crate) fn first_error<S>(errors: Vec<PlanError<S>>) -> PlanError<S> {
+    let mut errors: Vec<_> = errors
+        .into_iter()
+        .filter(|error| !matches!(error, PlanError::AlreadySatisfied))
+        .collect();
+    match errors
+        .iter()
+        .position(|error| matches!(error, PlanError::BudgetExhausted(_)))
+    {
+        Some(index) => errors.swap_remove(index),
+        None if errors.is_empty() => PlanError::AlreadySatisfied,
+        None => errors.swap_remove(0),
     }
 }
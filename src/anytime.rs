@@ -0,0 +1,369 @@
+use crate::{check_initial, Action, Cost, Goal, NodeOrder, Plan, PlanError, Search, SearchBudget};
+use std::hash::Hash;
+
+/// A plan found by an `AnytimePlanner`, with a bound on how far it may be from optimal.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnytimePlan<S, A, C = i32> {
+    /// The plan which was found.
+    pub plan: Plan<S, A, C>,
+    /// The plan costs at most this many times as much as the cheapest plan,
+    /// as long as the goal's heuristic never overestimates.
+    pub bound: f64,
+}
+
+/// Finds a plan quickly, then keeps searching for cheaper plans.
+///
+/// The planner runs weighted A* searches with a decreasing weight, starting high so that the first plan is found quickly.
+/// Each search only looks for plans cheaper than the best one so far, and stops after a search with a weight of 1,
+/// which finds the cheapest plan.
+///
+/// The planner is an iterator which yields each cheaper plan as it is found, so it can be stopped at any point.
+/// Its budget applies to all of its searches together.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+///
+/// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+/// struct Counter(i32);
+///
+/// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+/// enum Add {
+///     One,
+///     Five,
+/// }
+///
+/// impl Action<Counter> for Add {
+///     fn is_applicable(&self, _state: &Counter) -> bool {
+///         true
+///     }
+///
+///     fn apply_mut(&self, state: &mut Counter) {
+///         state.0 += match self {
+///             Add::One => 1,
+///             Add::Five => 5,
+///         };
+///     }
+///
+///     fn cost(&self, _state: &Counter) -> i32 {
+///         match self {
+///             Add::One => 1,
+///             Add::Five => 10,
+///         }
+///     }
+/// }
+///
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// struct Reach(i32);
+///
+/// impl Goal<Counter> for Reach {
+///     fn is_satisfied(&self, state: &Counter) -> bool {
+///         state.0 == self.0
+///     }
+///
+///     fn heuristic(&self, state: &Counter) -> i32 {
+///         (self.0 - state.0).max(0)
+///     }
+/// }
+///
+/// let mut planner = AnytimePlanner::new(Counter(0), vec![Add::One, Add::Five], Reach(5))
+///     .with_weights(3.0, 1.5);
+///
+/// // The first plan is found quickly
+/// let first = planner.next().unwrap();
+/// assert_eq!(first.plan.cost(), 10);
+/// assert_eq!(first.bound, 3.0);
+///
+/// // Later plans are cheaper
+/// let second = planner.next().unwrap();
+/// assert_eq!(second.plan.cost(), 5);
+/// assert_eq!(second.bound, 1.5);
+///
+/// // The last search proves the best plan is optimal
+/// assert!(planner.next().is_none());
+/// assert_eq!(planner.bound(), 1.0);
+/// ```
+pub struct AnytimePlanner<S, A, G, C = i32>
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    G: Goal<S, C>,
+    C: Cost,
+{
+    initial_state: S,
+    actions: Vec<A>,
+    goal: G,
+    budget: SearchBudget<C>,
+    weight: f64,
+    decrement: f64,
+    best: Option<Plan<S, A, C>>,
+    bound: f64,
+    error: Option<PlanError<S>>,
+    expanded: usize,
+    started: bool,
+    finished: bool,
+}
+
+/// How much the weight decreases with each search, unless set with `with_weights`.
+const DEFAULT_DECREMENT: f64 = 0.5;
+
+impl<S, A, G, C> AnytimePlanner<S, A, G, C>
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    G: Goal<S, C>,
+    C: Cost,
+{
+    /// Creates a planner from the given state to the goal, without a budget.
+    ///
+    /// The first search uses a weight of 3, which decreases by 0.5 with each search.
+    pub fn new(initial_state: S, actions: Vec<A>, goal: G) -> Self {
+        Self {
+            initial_state,
+            actions,
+            goal,
+            budget: SearchBudget::unlimited(),
+            weight: 3.0,
+            decrement: DEFAULT_DECREMENT,
+            best: None,
+            bound: f64::INFINITY,
+            error: None,
+            expanded: 0,
+            started: false,
+            finished: false,
+        }
+    }
+
+    /// Limits all of the planner's searches together by the given budget.
+    pub fn with_budget(mut self, budget: SearchBudget<C>) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Sets the weight of the first search, and how much it decreases with each search.
+    ///
+    /// Weights never go below 1. A decrement which isn't positive would never reach 1,
+    /// so the default of 0.5 is used instead.
+    pub fn with_weights(mut self, initial: f64, decrement: f64) -> Self {
+        self.weight = initial.max(1.0);
+        self.decrement = if decrement > 0.0 {
+            decrement
+        } else {
+            DEFAULT_DECREMENT
+        };
+        self
+    }
+
+    /// Returns the cheapest plan found so far.
+    pub fn best(&self) -> Option<&Plan<S, A, C>> {
+        self.best.as_ref()
+    }
+
+    /// Returns the suboptimality bound of the cheapest plan found so far.
+    ///
+    /// This may be lower than the bound the plan was yielded with, if later searches found no cheaper plan.
+    /// It is infinite until a plan is found.
+    pub fn bound(&self) -> f64 {
+        self.bound
+    }
+
+    /// Returns the reason no plan was found, once the planner has finished without finding one.
+    pub fn error(&self) -> Option<&PlanError<S>> {
+        self.error.as_ref()
+    }
+
+    /// Returns the total number of states expanded by all searches so far.
+    pub fn expanded(&self) -> usize {
+        self.expanded
+    }
+
+    /// Returns true if the planner will not search any more.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Stops the planner and returns the cheapest plan found so far.
+    pub fn into_best(self) -> Option<Plan<S, A, C>> {
+        self.best
+    }
+}
+
+impl<S, A, G, C> Iterator for AnytimePlanner<S, A, G, C>
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    G: Goal<S, C>,
+    C: Cost,
+{
+    type Item = AnytimePlan<S, A, C>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.started {
+            self.started = true;
+            match check_initial(&self.initial_state, &self.actions, &self.goal) {
+                Some(PlanError::AlreadySatisfied) => {
+                    let plan = Plan::from_actions(&self.initial_state, vec![]);
+                    self.best = Some(plan.clone());
+                    self.bound = 1.0;
+                    self.finished = true;
+                    return Some(AnytimePlan { plan, bound: 1.0 });
+                }
+                Some(error) => {
+                    self.error = Some(error);
+                    self.finished = true;
+                }
+                None => {}
+            }
+        }
+
+        while !self.finished {
+            let weight = self.weight;
+            self.weight = (weight - self.decrement).max(1.0);
+            self.finished = weight <= 1.0;
+
+            let budget = self.budget.after(self.expanded);
+            let order = NodeOrder::WeightedAStar(weight);
+            let mut search = Search::with_order(&self.initial_state, &self.goal, order);
+            // Only search for plans cheaper than the best so far
+            if let Some(best) = &self.best {
+                search = search.with_cost_below(best.cost());
+            }
+            let outcome = search.run(&self.actions[..], &self.goal, &budget);
+            self.expanded += search.expanded();
+            let spent = self.budget.is_spent(self.expanded);
+
+            match (search.result(outcome), &self.best) {
+                (Ok(plan), _) => {
+                    self.best = Some(plan.clone());
+                    self.bound = weight;
+                    return Some(AnytimePlan {
+                        plan,
+                        bound: weight,
+                    });
+                }
+                // No cheaper plan exists within this weight's bound
+                (Err(PlanError::Exhausted(_)), Some(_)) if !spent => self.bound = weight,
+                // The budget ran out or its limits pruned the search, so cheaper plans may exist
+                (Err(error), best) => {
+                    if best.is_none() {
+                        self.error = Some(error);
+                    }
+                    self.finished = true;
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    struct Position(i32, i32);
+
+    #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    enum Move {
+        Right,
+        Up,
+        Diagonal,
+    }
+
+    impl Action<Position> for Move {
+        fn is_applicable(&self, state: &Position) -> bool {
+            state.0 < 10 && state.1 < 10
+        }
+
+        fn apply_mut(&self, state: &mut Position) {
+            match self {
+                Move::Right => state.0 += 1,
+                Move::Up => state.1 += 1,
+                Move::Diagonal => {
+                    state.0 += 1;
+                    state.1 += 1;
+                }
+            }
+        }
+
+        fn cost(&self, _state: &Position) -> i32 {
+            match self {
+                Move::Right | Move::Up => 1,
+                Move::Diagonal => 3,
+            }
+        }
+    }
+
+    #[derive(PartialEq, Eq, Hash, Clone)]
+    struct Reach(i32, i32);
+
+    impl Goal<Position> for Reach {
+        fn is_satisfied(&self, state: &Position) -> bool {
+            state.0 == self.0 && state.1 == self.1
+        }
+
+        fn heuristic(&self, state: &Position) -> i32 {
+            (self.0 - state.0).max(0) + (self.1 - state.1).max(0)
+        }
+    }
+
+    fn anytime(goal: Reach) -> AnytimePlanner<Position, Move, Reach> {
+        let actions = vec![Move::Right, Move::Up, Move::Diagonal];
+        AnytimePlanner::new(Position(0, 0), actions, goal).with_weights(3.0, 1.5)
+    }
+
+    #[test]
+    fn anytime_improves() {
+        let mut planner = anytime(Reach(3, 3));
+        let plans: Vec<_> = planner
+            .by_ref()
+            .map(|found| (found.plan.cost(), found.bound))
+            .collect();
+        assert_eq!(plans, vec![(9, 3.0), (8, 1.5), (6, 1.0)]);
+        assert!(planner.is_finished());
+        assert_eq!(planner.bound(), 1.0);
+        assert_eq!(planner.best().unwrap().cost(), 6);
+        assert_eq!(planner.error(), None);
+
+        let mut planner = anytime(Reach(0, 0));
+        assert_eq!(planner.next().unwrap().bound, 1.0);
+        assert_eq!(planner.next(), None);
+
+        let mut planner = anytime(Reach(20, 20));
+        assert_eq!(planner.next(), None);
+        assert!(matches!(planner.error(), Some(PlanError::Exhausted(_))));
+    }
+
+    #[test]
+    fn anytime_budget() {
+        // The first plan is found, but there isn't enough budget left to improve it
+        let budget = SearchBudget::unlimited().with_max_expanded(4);
+        let mut planner = anytime(Reach(3, 3)).with_budget(budget);
+        assert_eq!(planner.next().unwrap().plan.cost(), 9);
+        assert_eq!(planner.next(), None);
+        assert_eq!(planner.expanded(), 4);
+        assert_eq!(planner.bound(), 3.0);
+        assert_eq!(planner.into_best().unwrap().cost(), 9);
+
+        // Cheaper plans are too deep, so the bound isn't tightened
+        let budget = SearchBudget::unlimited().with_max_depth(3);
+        let mut planner = anytime(Reach(3, 3)).with_budget(budget);
+        assert_eq!(planner.next().unwrap().plan.cost(), 9);
+        assert_eq!(planner.next(), None);
+        assert!(planner.is_finished());
+        assert_eq!(planner.bound(), 3.0);
+        assert_eq!(planner.error(), None);
+    }
+
+    #[test]
+    fn anytime_default_decrement() {
+        // The weight still decreases, so the planner proves its best plan is the cheapest
+        for decrement in [0.0, -1.0, f64::NAN] {
+            let mut planner = anytime(Reach(3, 3)).with_weights(3.0, decrement);
+            planner.by_ref().for_each(drop);
+            assert!(planner.is_finished());
+            assert_eq!(planner.bound(), 1.0, "{decrement}");
+            assert_eq!(planner.best().unwrap().cost(), 6);
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use crate::Cost;
+use std::error::Error;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Limits on how much work a search may do before giving up.
+///
+/// Every limit is optional, and the default budget is unlimited.
+/// When a limit is reached the search stops and reports `BudgetExhausted`,
+/// which is distinct from finding that no plan exists.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+/// use std::time::Duration;
+///
+/// let budget: SearchBudget = SearchBudget::unlimited()
+///     .with_max_expanded(1000)
+///     .with_max_depth(10)
+///     .with_timeout(Duration::from_millis(1));
+///
+/// assert_eq!(budget.max_expanded, Some(1000));
+/// assert_eq!(budget.max_depth, Some(10));
+/// assert_eq!(budget.max_cost, None);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SearchBudget<C = i32> {
+    /// The maximum number of states expanded by the search.
+    pub max_expanded: Option<usize>,
+    /// The maximum number of actions in a plan.
+    pub max_depth: Option<usize>,
+    /// The maximum total cost of a plan.
+    pub max_cost: Option<C>,
+    /// A point in time after which the search stops expanding states.
+    pub deadline: Option<Instant>,
+}
+
+impl<C> Default for SearchBudget<C> {
+    fn default() -> Self {
+        Self {
+            max_expanded: None,
+            max_depth: None,
+            max_cost: None,
+            deadline: None,
+        }
+    }
+}
+
+impl<C: Cost> SearchBudget<C> {
+    /// Returns a budget without any limits.
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// Limits the number of states the search may expand.
+    pub fn with_max_expanded(mut self, max_expanded: usize) -> Self {
+        self.max_expanded = Some(max_expanded);
+        self
+    }
+
+    /// Limits the number of actions in a plan.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Limits the total cost of a plan.
+    pub fn with_max_cost(mut self, max_cost: C) -> Self {
+        self.max_cost = Some(max_cost);
+        self
+    }
+
+    /// Stops the search at the given point in time.
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Stops the search once the given duration has passed, starting now.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        self.with_deadline(Instant::now() + timeout)
+    }
+
+    /// Returns the budget left over after the given number of states were expanded.
+    ///
+    /// This is used to share one budget between several searches.
+    pub(crate) fn after(&self, expanded: usize) -> Self {
+        Self {
+            max_expanded: self.max_expanded.map(|max| max.saturating_sub(expanded)),
+            ..*self
+        }
+    }
+
+    /// Returns true if the search may not expand any more states.
+    pub(crate) fn is_spent(&self, expanded: usize) -> bool {
+        self.max_expanded.is_some_and(|max| expanded >= max)
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// Returns true if a plan with the given length and cost may be searched further.
+    pub(crate) fn allows(&self, depth: usize, cost: C) -> bool {
+        self.max_depth.is_none_or(|max| depth <= max) && self.max_cost.is_none_or(|max| cost <= max)
+    }
+}
+
+/// Returned when a search stops because its `SearchBudget` ran out.
+///
+/// This means a plan may still exist, but it was not found within the given limits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BudgetExhausted;
+
+impl fmt::Display for BudgetExhausted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "search budget exhausted before a plan was found")
+    }
+}
+
+impl Error for BudgetExhausted {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_limits() {
+        let budget = SearchBudget::<i32>::unlimited();
+        assert!(!budget.is_spent(usize::MAX));
+        assert!(budget.allows(usize::MAX, i32::MAX));
+
+        let budget = budget
+            .with_max_expanded(10)
+            .with_max_depth(2)
+            .with_max_cost(5);
+        assert!(!budget.is_spent(9));
+        assert!(budget.is_spent(10));
+        assert!(budget.after(4).is_spent(6));
+        assert!(budget.allows(2, 5));
+        assert!(!budget.allows(3, 5));
+        assert!(!budget.allows(2, 6));
+
+        let budget = SearchBudget::<i32>::unlimited().with_deadline(Instant::now());
+        assert!(budget.is_spent(0));
+    }
+}
@@ -0,0 +1,354 @@
+use crate::{Action, Cost, Goal, Plan};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::hash::Hash;
+
+/// A state where the heuristic overestimates the cheapest cost to satisfy the goal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Inadmissible<S, A, C = i32> {
+    /// The state where the heuristic overestimates.
+    pub state: S,
+    /// The heuristic estimate for `state`.
+    pub heuristic: C,
+    /// The cost of `plan`, which is lower than the estimate.
+    pub cost_to_go: C,
+    /// The cheapest plan found from `state` to the goal.
+    pub plan: Plan<S, A, C>,
+}
+
+/// A transition across which the heuristic drops by more than the cost of the action.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Inconsistent<S, A, C = i32> {
+    /// The state before the action.
+    pub state: S,
+    /// The action taken from `state`.
+    pub action: A,
+    /// The state after the action.
+    pub next_state: S,
+    /// The cost of the action.
+    pub cost: C,
+    /// The heuristic estimate for `state`.
+    pub heuristic: C,
+    /// The heuristic estimate for `next_state`.
+    pub next_heuristic: C,
+}
+
+/// The violations found by `check_heuristic`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HeuristicReport<S, A, C = i32> {
+    /// The number of reachable states which were explored.
+    pub explored: usize,
+    /// Whether every reachable state was explored.
+    ///
+    /// If not, the cost to go is only known within the explored states and may be higher than the true cost,
+    /// so states close to the unexplored ones may be reported as inadmissible when they are not.
+    pub complete: bool,
+    /// Every explored state where the heuristic is higher than the cost to go.
+    pub inadmissible: Vec<Inadmissible<S, A, C>>,
+    /// Every explored transition where the heuristic is not consistent.
+    pub inconsistent: Vec<Inconsistent<S, A, C>>,
+}
+
+impl<S, A, C> HeuristicReport<S, A, C> {
+    /// Returns true if the heuristic never overestimated the cost to go.
+    pub fn is_admissible(&self) -> bool {
+        self.inadmissible.is_empty()
+    }
+
+    /// Returns true if the heuristic was consistent across every transition.
+    pub fn is_consistent(&self) -> bool {
+        self.inconsistent.is_empty()
+    }
+}
+
+/// A transition between two explored states.
+struct Transition<C> {
+    from: usize,
+    to: usize,
+    action: usize,
+    cost: C,
+}
+
+/// Checks the goal's heuristic against the true costs in the space reachable from the initial state.
+///
+/// At most `max_states` states are explored, in breadth-first order.
+/// The cheapest cost to satisfy the goal from each explored state is then found by searching backwards from the goal states.
+///
+/// A heuristic is *admissible* if it never overestimates this cost, which is needed for plans to be optimal.
+/// It is *consistent* if for every action, it never drops by more than the cost of that action,
+/// which is needed for plans to be optimal without expanding states more than once.
+/// Consistent heuristics which are zero in goal states are also admissible.
+///
+/// This is meant for tests and debugging, as it explores every reachable state.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+///
+/// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+/// struct Counter(i32);
+///
+/// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+/// struct Increment;
+///
+/// impl Action<Counter> for Increment {
+///     fn is_applicable(&self, state: &Counter) -> bool {
+///         state.0 < 10
+///     }
+///
+///     fn apply_mut(&self, state: &mut Counter) {
+///         state.0 += 1;
+///     }
+/// }
+///
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// struct Reach(i32);
+///
+/// impl Goal<Counter> for Reach {
+///     fn is_satisfied(&self, state: &Counter) -> bool {
+///         state.0 == self.0
+///     }
+///
+///     fn heuristic(&self, state: &Counter) -> i32 {
+///         // Overestimates by one
+///         (self.0 - state.0).max(0) + 1
+///     }
+/// }
+///
+/// let report = check_heuristic(&Counter(0), &[Increment], &Reach(5), 100);
+/// assert!(report.complete);
+/// assert_eq!(report.explored, 11);
+/// assert!(report.is_consistent());
+///
+/// // Every state up to and including the goal overestimates
+/// assert_eq!(report.inadmissible.len(), 6);
+/// let violation = &report.inadmissible[0];
+/// assert_eq!(violation.state, Counter(0));
+/// assert_eq!((violation.heuristic, violation.cost_to_go), (6, 5));
+/// assert_eq!(violation.plan.len(), 5);
+/// ```
+pub fn check_heuristic<S, A, G, C>(
+    initial_state: &S,
+    actions: &[A],
+    goal: &G,
+    max_states: usize,
+) -> HeuristicReport<S, A, C>
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    G: Goal<S, C>,
+    C: Cost,
+{
+    // Explore the reachable states
+    let mut states = vec![initial_state.clone()];
+    let mut index = HashMap::from([(initial_state.clone(), 0)]);
+    let mut transitions = Vec::new();
+    let mut queue = VecDeque::from([0]);
+    let mut complete = true;
+    while let Some(from) = queue.pop_front() {
+        for (action_index, action) in actions.iter().enumerate() {
+            if !action.is_applicable(&states[from]) {
+                continue;
+            }
+            let next = action.apply(&states[from]);
+            let to = match index.get(&next) {
+                Some(&to) => to,
+                None if states.len() < max_states => {
+                    let to = states.len();
+                    index.insert(next.clone(), to);
+                    states.push(next);
+                    queue.push_back(to);
+                    to
+                }
+                None => {
+                    complete = false;
+                    continue;
+                }
+            };
+            transitions.push(Transition {
+                from,
+                to,
+                action: action_index,
+                cost: action.cost(&states[from]),
+            });
+        }
+    }
+
+    // Search backwards from the goal states for the cheapest cost to go
+    let mut incoming: Vec<Vec<usize>> = vec![Vec::new(); states.len()];
+    for (i, transition) in transitions.iter().enumerate() {
+        incoming[transition.to].push(i);
+    }
+    let mut cost_to_go: Vec<Option<C>> = vec![None; states.len()];
+    let mut next: Vec<Option<usize>> = vec![None; states.len()];
+    let mut open = BinaryHeap::new();
+    for (i, state) in states.iter().enumerate() {
+        if goal.is_satisfied(state) {
+            cost_to_go[i] = Some(C::ZERO);
+            open.push(Reverse((C::ZERO, i)));
+        }
+    }
+    while let Some(Reverse((cost, to))) = open.pop() {
+        if cost_to_go[to].is_some_and(|best| best < cost) {
+            continue;
+        }
+        for &i in &incoming[to] {
+            let transition = &transitions[i];
+            let cost = cost + transition.cost;
+            if cost_to_go[transition.from].is_none_or(|best| cost < best) {
+                cost_to_go[transition.from] = Some(cost);
+                next[transition.from] = Some(i);
+                open.push(Reverse((cost, transition.from)));
+            }
+        }
+    }
+
+    let heuristics: Vec<C> = states.iter().map(|state| goal.heuristic(state)).collect();
+
+    let mut inadmissible = Vec::new();
+    for (i, state) in states.iter().enumerate() {
+        let Some(cost) = cost_to_go[i] else {
+            continue;
+        };
+        if heuristics[i] > cost {
+            let mut path = Vec::new();
+            let mut current = i;
+            while let Some(t) = next[current] {
+                path.push(actions[transitions[t].action].clone());
+                current = transitions[t].to;
+            }
+            inadmissible.push(Inadmissible {
+                state: state.clone(),
+                heuristic: heuristics[i],
+                cost_to_go: cost,
+                plan: Plan::from_actions(state, path),
+            });
+        }
+    }
+
+    let inconsistent = transitions
+        .iter()
+        .filter(|t| heuristics[t.from] > t.cost + heuristics[t.to])
+        .map(|t| Inconsistent {
+            state: states[t.from].clone(),
+            action: actions[t.action].clone(),
+            next_state: states[t.to].clone(),
+            cost: t.cost,
+            heuristic: heuristics[t.from],
+            next_heuristic: heuristics[t.to],
+        })
+        .collect();
+
+    HeuristicReport {
+        explored: states.len(),
+        complete,
+        inadmissible,
+        inconsistent,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    struct Position(i32);
+
+    #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    enum Move {
+        Step,
+        Jump,
+    }
+
+    impl Action<Position> for Move {
+        fn is_applicable(&self, state: &Position) -> bool {
+            state.0 < 10
+        }
+
+        fn apply_mut(&self, state: &mut Position) {
+            state.0 += match self {
+                Move::Step => 1,
+                Move::Jump => 3,
+            };
+        }
+
+        fn cost(&self, _state: &Position) -> i32 {
+            match self {
+                Move::Step => 1,
+                Move::Jump => 2,
+            }
+        }
+    }
+
+    /// Reaches a position, with a heuristic that is exact except in one state.
+    #[derive(PartialEq, Eq, Hash, Clone)]
+    struct Reach(i32, Option<(i32, i32)>);
+
+    impl Goal<Position> for Reach {
+        fn is_satisfied(&self, state: &Position) -> bool {
+            state.0 == self.0
+        }
+
+        fn heuristic(&self, state: &Position) -> i32 {
+            match self.1 {
+                Some((position, estimate)) if position == state.0 => estimate,
+                _ => {
+                    let distance = (self.0 - state.0).max(0);
+                    distance / 3 * 2 + distance % 3
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn check_exact() {
+        let actions = [Move::Step, Move::Jump];
+        let report = check_heuristic(&Position(0), &actions, &Reach(6, None), 100);
+        assert!(report.complete);
+        assert!(report.is_admissible());
+        assert!(report.is_consistent());
+
+        // Underestimating in one state keeps it admissible, but not consistent
+        let report = check_heuristic(&Position(0), &actions, &Reach(6, Some((3, 0))), 100);
+        assert!(report.is_admissible());
+        let violations: Vec<_> = report
+            .inconsistent
+            .iter()
+            .map(|v| (v.state.clone(), v.action.clone(), v.next_state.clone()))
+            .collect();
+        assert_eq!(
+            violations,
+            vec![
+                (Position(0), Move::Jump, Position(3)),
+                (Position(2), Move::Step, Position(3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_overestimate() {
+        let actions = [Move::Step, Move::Jump];
+        let report = check_heuristic(&Position(0), &actions, &Reach(6, Some((2, 5))), 100);
+        assert_eq!(report.inadmissible.len(), 1);
+        let violation = &report.inadmissible[0];
+        assert_eq!(violation.state, Position(2));
+        assert_eq!((violation.heuristic, violation.cost_to_go), (5, 3));
+        assert_eq!(violation.plan.cost(), 3);
+        assert_eq!(violation.plan.final_state(), Some(&Position(6)));
+
+        // The overestimate is also inconsistent with both actions out of the state
+        let violations: Vec<_> = report
+            .inconsistent
+            .iter()
+            .map(|v| (v.state.clone(), v.next_state.clone()))
+            .collect();
+        assert_eq!(
+            violations,
+            vec![(Position(2), Position(3)), (Position(2), Position(5))]
+        );
+
+        let report = check_heuristic(&Position(0), &actions, &Reach(6, Some((2, 5))), 4);
+        assert!(!report.complete);
+        assert_eq!(report.explored, 4);
+    }
+}
@@ -0,0 +1,256 @@
+use crate::{Action, Cost, Goal};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// A shared closure computing a value from a state.
+type StateFn<S, T> = Arc<dyn Fn(&S) -> T + Send + Sync>;
+
+/// A shared closure modifying a state.
+type StateMutFn<S> = Arc<dyn Fn(&mut S) + Send + Sync>;
+
+/// An action made from closures, for prototypes and tests where defining a type would be overkill.
+///
+/// Actions are identified by name, so two actions with the same name are equal even if their closures differ.
+/// Cloning an action only clones references to its closures.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+///
+/// let increment = FnAction::new("increment", |n: &i32| *n < 10, |n| *n += 1, |_| 1);
+/// let double = FnAction::new("double", |n: &i32| *n < 10, |n| *n *= 2, |_| 1);
+/// let reach = FnGoal::new("reach 12", |n: &i32| *n == 12, |n| i32::from(*n < 12));
+///
+/// let (path, cost) = plan(&3, &[increment, double.clone()], &reach).unwrap();
+/// assert_eq!(path, vec![double.clone(), double]);
+/// assert_eq!(cost, 2);
+/// ```
+pub struct FnAction<S, C = i32> {
+    name: Arc<str>,
+    is_applicable: StateFn<S, bool>,
+    apply: StateMutFn<S>,
+    cost: StateFn<S, C>,
+}
+
+impl<S, C> FnAction<S, C> {
+    /// Creates an action from closures which check whether it is applicable, apply it, and return its cost.
+    pub fn new(
+        name: impl Into<Arc<str>>,
+        is_applicable: impl Fn(&S) -> bool + Send + Sync + 'static,
+        apply: impl Fn(&mut S) + Send + Sync + 'static,
+        cost: impl Fn(&S) -> C + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            is_applicable: Arc::new(is_applicable),
+            apply: Arc::new(apply),
+            cost: Arc::new(cost),
+        }
+    }
+
+    /// Returns the name of the action.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<S, C> Clone for FnAction<S, C> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            is_applicable: self.is_applicable.clone(),
+            apply: self.apply.clone(),
+            cost: self.cost.clone(),
+        }
+    }
+}
+
+impl<S, C> PartialEq for FnAction<S, C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl<S, C> Eq for FnAction<S, C> {}
+
+impl<S, C> Hash for FnAction<S, C> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+    }
+}
+
+impl<S, C> fmt::Debug for FnAction<S, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FnAction").field(&self.name).finish()
+    }
+}
+
+impl<S, C> Action<S, C> for FnAction<S, C>
+where
+    S: Clone + Hash + Eq,
+    C: Cost,
+{
+    fn is_applicable(&self, state: &S) -> bool {
+        (self.is_applicable)(state)
+    }
+
+    fn apply_mut(&self, state: &mut S) {
+        (self.apply)(state)
+    }
+
+    fn cost(&self, state: &S) -> C {
+        (self.cost)(state)
+    }
+}
+
+/// A goal made from closures, for prototypes and tests where defining a type would be overkill.
+///
+/// Goals are identified by name, like `FnAction`s. They have a priority of zero unless one is given.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+///
+/// let increment = FnAction::new("increment", |n: &i32| *n < 10, |n| *n += 1, |_| 1);
+/// let goals = vec![
+///     FnGoal::new("reach 3", |n: &i32| *n >= 3, |n| (3 - n).max(0)).with_priority(|_| 1),
+///     FnGoal::new("reach 5", |n: &i32| *n >= 5, |n| (5 - n).max(0)).with_priority(|n| *n),
+/// ];
+///
+/// let mut agent = Agent::new(0, vec![increment], goals);
+/// let (goal, _, cost) = agent.plan_dynamic().unwrap();
+/// assert_eq!(goal.name(), "reach 3");
+/// assert_eq!(cost, 3);
+///
+/// agent.state = 2;
+/// let (goal, _, cost) = agent.plan_dynamic().unwrap();
+/// assert_eq!(goal.name(), "reach 5");
+/// assert_eq!(cost, 3);
+/// ```
+pub struct FnGoal<S, C = i32> {
+    name: Arc<str>,
+    is_satisfied: StateFn<S, bool>,
+    heuristic: StateFn<S, C>,
+    priority: Option<StateFn<S, C>>,
+}
+
+impl<S, C> FnGoal<S, C> {
+    /// Creates a goal from closures which check whether it is satisfied, and estimate the cost to satisfy it.
+    pub fn new(
+        name: impl Into<Arc<str>>,
+        is_satisfied: impl Fn(&S) -> bool + Send + Sync + 'static,
+        heuristic: impl Fn(&S) -> C + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            is_satisfied: Arc::new(is_satisfied),
+            heuristic: Arc::new(heuristic),
+            priority: None,
+        }
+    }
+
+    /// Sets a closure returning the priority of the goal in a state.
+    pub fn with_priority(mut self, priority: impl Fn(&S) -> C + Send + Sync + 'static) -> Self {
+        self.priority = Some(Arc::new(priority));
+        self
+    }
+
+    /// Returns the name of the goal.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<S, C> Clone for FnGoal<S, C> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            is_satisfied: self.is_satisfied.clone(),
+            heuristic: self.heuristic.clone(),
+            priority: self.priority.clone(),
+        }
+    }
+}
+
+impl<S, C> PartialEq for FnGoal<S, C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl<S, C> Eq for FnGoal<S, C> {}
+
+impl<S, C> Hash for FnGoal<S, C> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+    }
+}
+
+impl<S, C> fmt::Debug for FnGoal<S, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FnGoal").field(&self.name).finish()
+    }
+}
+
+impl<S, C> Goal<S, C> for FnGoal<S, C>
+where
+    S: Clone + Hash + Eq,
+    C: Cost,
+{
+    fn is_satisfied(&self, state: &S) -> bool {
+        (self.is_satisfied)(state)
+    }
+
+    fn heuristic(&self, state: &S) -> C {
+        (self.heuristic)(state)
+    }
+
+    fn priority(&self, state: &S) -> C {
+        self.priority
+            .as_ref()
+            .map_or(C::ZERO, |priority| priority(state))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{plan, try_plan, SearchBudget};
+    use std::collections::HashSet;
+
+    #[test]
+    fn fn_identity() {
+        let a: FnAction<i32> = FnAction::new("step", |_| true, |n| *n += 1, |_| 1);
+        let b: FnAction<i32> = FnAction::new("step", |_| false, |n| *n -= 1, |_| 5);
+        let c: FnAction<i32> = FnAction::new("jump", |_| true, |n| *n += 2, |_| 1);
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_eq!(a.clone().name(), "step");
+        assert_eq!(format!("{a:?}"), "FnAction(\"step\")");
+
+        let set: HashSet<_> = [a, b, c].into_iter().collect();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn fn_planning() {
+        // Costs and heuristics may depend on the state
+        let walk = FnAction::new("walk", |n: &u64| *n < 20, |n| *n += 1, |_| 2u64);
+        let run = FnAction::new("run", |n: &u64| *n < 20, |n| *n += 3, |n| 3 + n / 5);
+        let goal = FnGoal::new(
+            "reach 9",
+            |n: &u64| *n == 9,
+            |n| 9u64.saturating_sub(*n) * 2 / 3,
+        );
+
+        let (path, cost) = plan(&0, &[walk.clone(), run.clone()], &goal).unwrap();
+        assert_eq!(path, vec![run.clone(), run.clone(), run.clone()]);
+        assert_eq!(cost, 3 + 3 + 4);
+        assert_eq!(Goal::<u64, u64>::priority(&goal, &0), 0);
+
+        let goal = FnGoal::new("reach 30", |n: &u64| *n == 30, |_| 0);
+        let result = try_plan(&0, &[walk, run], &goal, &SearchBudget::unlimited());
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,122 @@
+use std::fmt::Debug;
+use std::ops::Add;
+
+/// A numeric type used for the costs of actions, and the heuristics and priorities of goals.
+///
+/// Costs are `i32` by default, and every primitive integer type implements this trait.
+/// Other types, such as fixed-point numbers, can implement it too as long as they are totally ordered
+/// and closed under addition.
+/// With the `ordered-float` feature, `OrderedFloat<f32>` and `OrderedFloat<f64>` implement it as well.
+///
+/// Plans are only guaranteed to be optimal if costs are never negative.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+/// use std::ops::Add;
+///
+/// /// An amount of money in hundredths of a coin.
+/// #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// struct Money(u64);
+///
+/// impl Add for Money {
+///     type Output = Money;
+///
+///     fn add(self, other: Money) -> Money {
+///         Money(self.0 + other.0)
+///     }
+/// }
+///
+/// impl Cost for Money {
+///     const ZERO: Money = Money(0);
+///     const ONE: Money = Money(100);
+///
+///     fn scale(self, factor: f64) -> Money {
+///         Money((self.0 as f64 * factor).floor() as u64)
+///     }
+/// }
+///
+/// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+/// struct Wallet {
+///     has_bread: bool,
+/// }
+///
+/// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+/// struct BuyBread;
+///
+/// impl Action<Wallet, Money> for BuyBread {
+///     fn is_applicable(&self, state: &Wallet) -> bool {
+///         !state.has_bread
+///     }
+///
+///     fn apply_mut(&self, state: &mut Wallet) {
+///         state.has_bread = true;
+///     }
+///
+///     fn cost(&self, _state: &Wallet) -> Money {
+///         Money(250)
+///     }
+/// }
+///
+/// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+/// struct Fed;
+///
+/// impl Goal<Wallet, Money> for Fed {
+///     fn is_satisfied(&self, state: &Wallet) -> bool {
+///         state.has_bread
+///     }
+/// }
+///
+/// let (path, cost) = plan(&Wallet { has_bread: false }, &[BuyBread], &Fed).unwrap();
+/// assert_eq!(path, vec![BuyBread]);
+/// assert_eq!(cost, Money(250));
+/// ```
+pub trait Cost: Copy + Ord + Add<Output = Self> + Debug {
+    /// The cost of doing nothing.
+    const ZERO: Self;
+
+    /// The cost of an action which doesn't implement `Action::cost`.
+    const ONE: Self;
+
+    /// Multiplies the cost by the given factor, rounding down if the type can't represent the result.
+    ///
+    /// This is used to weight heuristics, as in `SearchStrategy::WeightedAStar`.
+    fn scale(self, factor: f64) -> Self;
+}
+
+macro_rules! impl_cost {
+    ($($t:ty),*) => {
+        $(
+            impl Cost for $t {
+                const ZERO: Self = 0;
+                const ONE: Self = 1;
+
+                fn scale(self, factor: f64) -> Self {
+                    (self as f64 * factor).floor() as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_cost!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+#[cfg(feature = "ordered-float")]
+impl Cost for ordered_float::OrderedFloat<f32> {
+    const ZERO: Self = ordered_float::OrderedFloat(0.0);
+    const ONE: Self = ordered_float::OrderedFloat(1.0);
+
+    fn scale(self, factor: f64) -> Self {
+        ordered_float::OrderedFloat(self.0 * factor as f32)
+    }
+}
+
+#[cfg(feature = "ordered-float")]
+impl Cost for ordered_float::OrderedFloat<f64> {
+    const ZERO: Self = ordered_float::OrderedFloat(0.0);
+    const ONE: Self = ordered_float::OrderedFloat(1.0);
+
+    fn scale(self, factor: f64) -> Self {
+        ordered_float::OrderedFloat(self.0 * factor)
+    }
+}
@@ -0,0 +1,455 @@
+use crate::{Agent, Cost, FactGoal, FactValue, RelaxedHeuristic, StripsAction, WorldState};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+use std::sync::Arc;
+
+/// An agent built from an `AgentDefinition`, which plans over named facts.
+pub type DataAgent<C = i32> = Agent<WorldState, DataAction<C>, DataGoal<C>>;
+
+/// An action of a `DataAgent`, over the indices of named facts.
+type DataAction<C> = StripsAction<usize, C>;
+
+/// A goal of a `DataAgent`, over the indices of named facts.
+type DataGoal<C> = FactGoal<usize, C>;
+
+/// An agent's initial state, actions and goals, described by named facts so that they can be written in data files.
+///
+/// Definitions can be read from any format supported by `serde`, such as RON, JSON or TOML,
+/// and built into a `DataAgent` at runtime without recompiling.
+/// Facts are booleans or integers, and each name is given a key in a `FactNames` when the definition is built.
+///
+/// Actions cost 1 unless a cost is given, and goals have a priority of 0 unless one is given.
+/// Goals use the `HMax` heuristic unless another `RelaxedHeuristic` is given.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+/// # use std::collections::BTreeMap;
+///
+/// let facts = |entries: &[(&str, bool)]| -> BTreeMap<String, FactValue> {
+///     entries.iter().map(|(name, value)| (name.to_string(), (*value).into())).collect()
+/// };
+///
+/// // Usually read from a file with a serde format
+/// let definition: AgentDefinition = AgentDefinition {
+///     state: facts(&[("has_axe", false), ("has_wood", false)]),
+///     actions: vec![
+///         ActionDefinition::new("get axe", facts(&[("has_axe", false)]), facts(&[("has_axe", true)])),
+///         ActionDefinition::new("chop wood", facts(&[("has_axe", true)]), facts(&[("has_wood", true)])),
+///     ],
+///     goals: vec![GoalDefinition::new("gather wood", facts(&[("has_wood", true)]))],
+/// };
+///
+/// let mut names = FactNames::new();
+/// let mut agent = definition.build(&mut names).unwrap();
+/// let (goal, plan, cost) = agent.plan_dynamic().unwrap();
+/// assert_eq!(goal.name(), "gather wood");
+/// assert_eq!(plan.iter().map(|action| action.name()).collect::<Vec<_>>(), ["get axe", "chop wood"]);
+/// assert_eq!(cost, 2);
+///
+/// let key = names.get("has_wood").unwrap();
+/// assert_eq!(agent.state.get_bool(key), Some(false));
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(bound(serialize = "C: Serialize", deserialize = "C: Cost + Deserialize<'de>"))]
+pub struct AgentDefinition<C = i32> {
+    #[serde(default)]
+    pub state: BTreeMap<String, FactValue>,
+    #[serde(default)]
+    pub actions: Vec<ActionDefinition<C>>,
+    #[serde(default)]
+    pub goals: Vec<GoalDefinition<C>>,
+}
+
+/// An action in an `AgentDefinition`, which is built into a `StripsAction`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(bound(serialize = "C: Serialize", deserialize = "C: Cost + Deserialize<'de>"))]
+pub struct ActionDefinition<C = i32> {
+    pub name: String,
+    /// The facts which must hold for the action to be applicable.
+    #[serde(default)]
+    pub requires: BTreeMap<String, FactValue>,
+    /// The facts which are set when the action is applied.
+    #[serde(default)]
+    pub effects: BTreeMap<String, FactValue>,
+    #[serde(default = "one")]
+    pub cost: C,
+}
+
+/// A goal in an `AgentDefinition`, which is built into a `FactGoal`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(bound(serialize = "C: Serialize", deserialize = "C: Cost + Deserialize<'de>"))]
+pub struct GoalDefinition<C = i32> {
+    pub name: String,
+    /// The facts which must hold for the goal to be satisfied.
+    pub condition: BTreeMap<String, FactValue>,
+    #[serde(default = "zero")]
+    pub priority: C,
+    #[serde(default)]
+    pub heuristic: RelaxedHeuristic,
+}
+
+fn one<C: Cost>() -> C {
+    C::ONE
+}
+
+fn zero<C: Cost>() -> C {
+    C::ZERO
+}
+
+impl<C: Cost> ActionDefinition<C> {
+    /// Creates an action definition with a cost of 1.
+    pub fn new(
+        name: impl Into<String>,
+        requires: BTreeMap<String, FactValue>,
+        effects: BTreeMap<String, FactValue>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            requires,
+            effects,
+            cost: C::ONE,
+        }
+    }
+}
+
+impl<C: Cost> GoalDefinition<C> {
+    /// Creates a goal definition with a priority of 0, using the `HMax` heuristic.
+    pub fn new(name: impl Into<String>, condition: BTreeMap<String, FactValue>) -> Self {
+        Self {
+            name: name.into(),
+            condition,
+            priority: C::ZERO,
+            heuristic: RelaxedHeuristic::default(),
+        }
+    }
+}
+
+/// The reason an `AgentDefinition` could not be built.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DefinitionError {
+    /// The fact is used as a boolean in one place and an integer in another.
+    MixedTypes(String),
+    /// More than one action has this name.
+    DuplicateAction(String),
+    /// More than one goal has this name.
+    DuplicateGoal(String),
+}
+
+impl fmt::Display for DefinitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DefinitionError::MixedTypes(fact) => {
+                write!(f, "fact `{fact}` is used as both a boolean and an integer")
+            }
+            DefinitionError::DuplicateAction(name) => write!(f, "action `{name}` is defined twice"),
+            DefinitionError::DuplicateGoal(name) => write!(f, "goal `{name}` is defined twice"),
+        }
+    }
+}
+
+impl Error for DefinitionError {}
+
+/// The keys given to named facts, so that states can be read and written by name.
+///
+/// The same names should be used whenever a definition is built or reloaded for an agent,
+/// so that each fact keeps the same key.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FactNames {
+    names: Vec<String>,
+    keys: HashMap<String, usize>,
+}
+
+impl FactNames {
+    /// Creates an empty set of names.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the key for the name, giving it a new key if it doesn't have one.
+    pub fn key(&mut self, name: &str) -> usize {
+        if let Some(&key) = self.keys.get(name) {
+            return key;
+        }
+        let key = self.names.len();
+        self.names.push(name.to_string());
+        self.keys.insert(name.to_string(), key);
+        key
+    }
+
+    /// Returns the key for the name, if it has one.
+    pub fn get(&self, name: &str) -> Option<usize> {
+        self.keys.get(name).copied()
+    }
+
+    /// Returns the name of the key.
+    pub fn name(&self, key: usize) -> Option<&str> {
+        self.names.get(key).map(String::as_str)
+    }
+
+    /// Returns the number of named facts.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Returns true if there are no named facts.
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Returns a state with the named facts, giving new keys to names which don't have one.
+    pub fn state(&mut self, facts: &BTreeMap<String, FactValue>) -> WorldState {
+        let mut state = WorldState::new();
+        for (name, &value) in facts {
+            state.set(self.key(name), value);
+        }
+        state
+    }
+
+    /// Returns the facts of a state by name, skipping any keys without a name.
+    pub fn describe(&self, state: &WorldState) -> BTreeMap<String, FactValue> {
+        state
+            .facts()
+            .into_iter()
+            .filter_map(|(key, value)| Some((self.name(key)?.to_string(), value)))
+            .collect()
+    }
+}
+
+impl<C: Cost + Hash> AgentDefinition<C> {
+    /// Builds an agent from the definition, giving keys to its facts in the given names.
+    pub fn build(&self, names: &mut FactNames) -> Result<DataAgent<C>, DefinitionError> {
+        self.validate(&BTreeMap::new())?;
+        let state = names.state(&self.state);
+        let (actions, goals) = self.actions_and_goals(names);
+        Ok(Agent::new(state, actions, goals))
+    }
+
+    /// Replaces the actions and goals of an agent built from another definition, keeping its current state.
+    ///
+    /// Facts in the definition's state which the agent's state doesn't have are added to it.
+    /// The agent is left unchanged if the definition is invalid,
+    /// including when it uses a fact of the agent's state with another type.
+    pub fn reload(
+        &self,
+        agent: &mut DataAgent<C>,
+        names: &mut FactNames,
+    ) -> Result<(), DefinitionError> {
+        self.validate(&names.describe(&agent.state))?;
+        let mut state = agent.state.clone();
+        for (key, value) in names.state(&self.state).facts() {
+            if !state.is_set(key) {
+                state.set(key, value);
+            }
+        }
+        let (actions, goals) = self.actions_and_goals(names);
+        *agent = Agent::new(state, actions, goals);
+        Ok(())
+    }
+
+    /// Checks the definition, and that it uses the facts of the current state with the same types.
+    fn validate(&self, current: &BTreeMap<String, FactValue>) -> Result<(), DefinitionError> {
+        let mut kinds = HashMap::new();
+        let all_facts = self
+            .actions
+            .iter()
+            .flat_map(|action| action.requires.iter().chain(&action.effects))
+            .chain(self.goals.iter().flat_map(|goal| &goal.condition))
+            .chain(&self.state)
+            .chain(current);
+        for (name, value) in all_facts {
+            let is_bool = matches!(value, FactValue::Bool(_));
+            if *kinds.entry(name).or_insert(is_bool) != is_bool {
+                return Err(DefinitionError::MixedTypes(name.clone()));
+            }
+        }
+
+        let mut action_names = HashMap::new();
+        for action in &self.actions {
+            if action_names.insert(&action.name, ()).is_some() {
+                return Err(DefinitionError::DuplicateAction(action.name.clone()));
+            }
+        }
+        let mut goal_names = HashMap::new();
+        for goal in &self.goals {
+            if goal_names.insert(&goal.name, ()).is_some() {
+                return Err(DefinitionError::DuplicateGoal(goal.name.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    fn actions_and_goals(&self, names: &mut FactNames) -> (Vec<DataAction<C>>, Vec<DataGoal<C>>) {
+        let actions: Vec<_> = self
+            .actions
+            .iter()
+            .map(|definition| {
+                let mut action = StripsAction::new(&definition.name).with_cost(definition.cost);
+                for (name, &value) in &definition.requires {
+                    action = action.requires(names.key(name), value);
+                }
+                for (name, &value) in &definition.effects {
+                    action = action.sets(names.key(name), value);
+                }
+                action
+            })
+            .collect();
+        let shared: Arc<[_]> = actions.clone().into();
+        let goals = self
+            .goals
+            .iter()
+            .map(|definition| {
+                FactGoal::new(names.state(&definition.condition), shared.clone())
+                    .with_name(&definition.name)
+                    .with_priority(definition.priority)
+                    .with_heuristic(definition.heuristic)
+            })
+            .collect();
+        (actions, goals)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RON: &str = r#"(
+        state: { "has_axe": false, "wood": 0, "warm": false },
+        actions: [
+            (name: "get axe", requires: { "has_axe": false }, effects: { "has_axe": true }, cost: 2),
+            (name: "chop wood", requires: { "has_axe": true, "wood": 0 }, effects: { "wood": 3 }),
+            (name: "light fire", requires: { "wood": 3 }, effects: { "wood": 0, "warm": true }),
+        ],
+        goals: [
+            (name: "keep warm", condition: { "warm": true }, priority: 2, heuristic: FastForward),
+            (name: "stock wood", condition: { "wood": 3 }),
+        ],
+    )"#;
+
+    const JSON: &str = r#"{
+        "state": { "has_axe": false, "wood": 0, "warm": false },
+        "actions": [
+            { "name": "get axe", "requires": { "has_axe": false }, "effects": { "has_axe": true }, "cost": 2 },
+            { "name": "chop wood", "requires": { "has_axe": true, "wood": 0 }, "effects": { "wood": 3 } },
+            { "name": "light fire", "requires": { "wood": 3 }, "effects": { "wood": 0, "warm": true } }
+        ],
+        "goals": [
+            { "name": "keep warm", "condition": { "warm": true }, "priority": 2, "heuristic": "FastForward" },
+            { "name": "stock wood", "condition": { "wood": 3 } }
+        ]
+    }"#;
+
+    const TOML: &str = r#"
+        state = { has_axe = false, wood = 0, warm = false }
+
+        [[actions]]
+        name = "get axe"
+        requires = { has_axe = false }
+        effects = { has_axe = true }
+        cost = 2
+
+        [[actions]]
+        name = "chop wood"
+        requires = { has_axe = true, wood = 0 }
+        effects = { wood = 3 }
+
+        [[actions]]
+        name = "light fire"
+        requires = { wood = 3 }
+        effects = { wood = 0, warm = true }
+
+        [[goals]]
+        name = "keep warm"
+        condition = { warm = true }
+        priority = 2
+        heuristic = "FastForward"
+
+        [[goals]]
+        name = "stock wood"
+        condition = { wood = 3 }
+    "#;
+
+    #[test]
+    fn definition_formats() {
+        let definition: AgentDefinition = ron::from_str(RON).unwrap();
+        assert_eq!(
+            serde_json::from_str::<AgentDefinition>(JSON).unwrap(),
+            definition
+        );
+        assert_eq!(toml::from_str::<AgentDefinition>(TOML).unwrap(), definition);
+        assert_eq!(definition.actions[1].cost, 1);
+        assert_eq!(definition.goals[1].priority, 0);
+
+        let mut names = FactNames::new();
+        let mut agent = definition.build(&mut names).unwrap();
+        assert_eq!(names.len(), 3);
+        let (goal, plan, cost) = agent.plan_dynamic().unwrap();
+        assert_eq!(goal.name(), "keep warm");
+        let plan: Vec<_> = plan.iter().map(|action| action.name()).collect();
+        assert_eq!(plan, ["get axe", "chop wood", "light fire"]);
+        assert_eq!(cost, 4);
+
+        let state = plan
+            .iter()
+            .map(|name| {
+                agent
+                    .actions
+                    .iter()
+                    .find(|action| action.name() == *name)
+                    .unwrap()
+            })
+            .fold(agent.state.clone(), |state, action| {
+                crate::Action::apply(action, &state)
+            });
+        let mut expected = definition.state.clone();
+        expected.insert("has_axe".to_string(), true.into());
+        expected.insert("warm".to_string(), true.into());
+        assert_eq!(names.describe(&state), expected);
+    }
+
+    #[test]
+    fn definition_reload() {
+        let definition: AgentDefinition = ron::from_str(RON).unwrap();
+        let mut names = FactNames::new();
+        let mut agent = definition.build(&mut names).unwrap();
+        let has_axe = names.get("has_axe").unwrap();
+        agent.state.set(has_axe, true);
+
+        // Designers reprioritize the goals and add a new fact
+        let mut changed = definition.clone();
+        changed.state.insert("fed".to_string(), false.into());
+        changed.goals[0].priority = 0;
+        changed.goals[1].priority = 5;
+        changed.reload(&mut agent, &mut names).unwrap();
+        assert_eq!(agent.state.get_bool(has_axe), Some(true));
+        assert_eq!(agent.state.get_bool(names.get("fed").unwrap()), Some(false));
+        assert_eq!(agent.goals[0].name(), "stock wood");
+
+        let mut invalid = definition.clone();
+        invalid.goals[1]
+            .condition
+            .insert("warm".to_string(), 1.into());
+        assert_eq!(
+            invalid.reload(&mut agent, &mut names),
+            Err(DefinitionError::MixedTypes("warm".to_string()))
+        );
+        assert_eq!(agent.goals[0].name(), "stock wood");
+
+        // The agent's state is a boolean, so it can't become an integer
+        let mut retyped = definition.clone();
+        retyped.state.insert("fed".to_string(), 0.into());
+        assert_eq!(
+            retyped.reload(&mut agent, &mut names),
+            Err(DefinitionError::MixedTypes("fed".to_string()))
+        );
+        assert_eq!(agent.state.get_bool(names.get("fed").unwrap()), Some(false));
+
+        let mut invalid = definition;
+        invalid.actions.push(invalid.actions[0].clone());
+        let error = invalid.build(&mut names).unwrap_err();
+        assert_eq!(error.to_string(), "action `get axe` is defined twice");
+    }
+}
@@ -0,0 +1,337 @@
+use crate::{Action, Cost, Goal};
+use std::any::Any;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// An action of any type, so that actions from different modules can be planned with together.
+///
+/// Each action is registered with an id, which identifies it in plans.
+/// Two actions with the same id are equal, so ids should be unique among the actions of an agent,
+/// for example by prefixing them with the name of the module which registers them.
+/// Cloning an action only clones a reference to it.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+///
+/// #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// struct State {
+///     wood: u32,
+///     warm: bool,
+/// }
+///
+/// // Defined by one module
+/// struct Chop;
+///
+/// impl Action<State> for Chop {
+///     fn is_applicable(&self, state: &State) -> bool {
+///         state.wood < 3
+///     }
+///
+///     fn apply_mut(&self, state: &mut State) {
+///         state.wood += 1;
+///     }
+/// }
+///
+/// // Defined by another module
+/// struct Burn(u32);
+///
+/// impl Action<State> for Burn {
+///     fn is_applicable(&self, state: &State) -> bool {
+///         state.wood >= self.0
+///     }
+///
+///     fn apply_mut(&self, state: &mut State) {
+///         state.wood -= self.0;
+///         state.warm = true;
+///     }
+/// }
+///
+/// let actions = vec![
+///     DynAction::new("forestry/chop", Chop),
+///     DynAction::new("fire/burn", Burn(2)),
+/// ];
+/// let goal = DynGoal::new("keep warm", FnGoal::new("warm", |state: &State| state.warm, |_| 0));
+///
+/// let agent = Agent::new(State { wood: 0, warm: false }, actions, vec![goal]);
+/// let (_, plan, cost) = agent.plan_constant().unwrap();
+/// let ids: Vec<_> = plan.iter().map(|action| action.id()).collect();
+/// assert_eq!(ids, vec!["forestry/chop", "forestry/chop", "fire/burn"]);
+/// assert_eq!(cost, 3);
+/// assert_eq!(plan[2].downcast_ref::<Burn>().unwrap().0, 2);
+/// ```
+pub struct DynAction<S, C = i32> {
+    id: Arc<str>,
+    action: Arc<dyn Action<S, C> + Send + Sync>,
+    any: Arc<dyn Any + Send + Sync>,
+}
+
+impl<S, C> DynAction<S, C>
+where
+    S: Clone + Hash + Eq,
+    C: Cost,
+{
+    /// Registers an action with the given id.
+    pub fn new<A>(id: impl Into<Arc<str>>, action: A) -> Self
+    where
+        A: Action<S, C> + Send + Sync + 'static,
+    {
+        let action = Arc::new(action);
+        Self {
+            id: id.into(),
+            action: action.clone(),
+            any: action,
+        }
+    }
+}
+
+impl<S, C> DynAction<S, C> {
+    /// Returns the id the action was registered with.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the action if it has the given type.
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.any.downcast_ref()
+    }
+}
+
+impl<S, C> Clone for DynAction<S, C> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            action: self.action.clone(),
+            any: self.any.clone(),
+        }
+    }
+}
+
+impl<S, C> PartialEq for DynAction<S, C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<S, C> Eq for DynAction<S, C> {}
+
+impl<S, C> Hash for DynAction<S, C> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl<S, C> fmt::Debug for DynAction<S, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DynAction").field(&self.id).finish()
+    }
+}
+
+impl<S, C> Action<S, C> for DynAction<S, C>
+where
+    S: Clone + Hash + Eq,
+    C: Cost,
+{
+    fn is_applicable(&self, state: &S) -> bool {
+        self.action.is_applicable(state)
+    }
+
+    fn apply_mut(&self, state: &mut S) {
+        self.action.apply_mut(state)
+    }
+
+    fn apply(&self, state: &S) -> S {
+        self.action.apply(state)
+    }
+
+    fn cost(&self, state: &S) -> C {
+        self.action.cost(state)
+    }
+}
+
+/// A goal of any type, so that goals from different modules can be planned for by one agent.
+///
+/// Goals are registered with an id, like `DynAction`s.
+pub struct DynGoal<S, C = i32> {
+    id: Arc<str>,
+    goal: Arc<dyn Goal<S, C> + Send + Sync>,
+    any: Arc<dyn Any + Send + Sync>,
+}
+
+impl<S, C> DynGoal<S, C>
+where
+    S: Clone + Hash + Eq,
+    C: Cost,
+{
+    /// Registers a goal with the given id.
+    pub fn new<G>(id: impl Into<Arc<str>>, goal: G) -> Self
+    where
+        G: Goal<S, C> + Send + Sync + 'static,
+    {
+        let goal = Arc::new(goal);
+        Self {
+            id: id.into(),
+            goal: goal.clone(),
+            any: goal,
+        }
+    }
+}
+
+impl<S, C> DynGoal<S, C> {
+    /// Returns the id the goal was registered with.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the goal if it has the given type.
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.any.downcast_ref()
+    }
+}
+
+impl<S, C> Clone for DynGoal<S, C> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            goal: self.goal.clone(),
+            any: self.any.clone(),
+        }
+    }
+}
+
+impl<S, C> PartialEq for DynGoal<S, C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<S, C> Eq for DynGoal<S, C> {}
+
+impl<S, C> Hash for DynGoal<S, C> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl<S, C> fmt::Debug for DynGoal<S, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DynGoal").field(&self.id).finish()
+    }
+}
+
+impl<S, C> Goal<S, C> for DynGoal<S, C>
+where
+    S: Clone + Hash + Eq,
+    C: Cost,
+{
+    fn is_satisfied(&self, state: &S) -> bool {
+        self.goal.is_satisfied(state)
+    }
+
+    fn heuristic(&self, state: &S) -> C {
+        self.goal.heuristic(state)
+    }
+
+    fn priority(&self, state: &S) -> C {
+        self.goal.priority(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Agent, FnAction, FnGoal};
+    use std::collections::HashSet;
+
+    #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    enum Step {
+        Forward,
+        Back,
+    }
+
+    impl Action<i32> for Step {
+        fn is_applicable(&self, state: &i32) -> bool {
+            state.abs() < 10
+        }
+
+        fn apply_mut(&self, state: &mut i32) {
+            match self {
+                Step::Forward => *state += 1,
+                Step::Back => *state -= 1,
+            }
+        }
+    }
+
+    struct Leap(i32);
+
+    impl Action<i32> for Leap {
+        fn is_applicable(&self, state: &i32) -> bool {
+            state.abs() < 10
+        }
+
+        fn apply_mut(&self, state: &mut i32) {
+            *state += self.0;
+        }
+
+        fn cost(&self, _state: &i32) -> i32 {
+            3
+        }
+    }
+
+    #[test]
+    fn dyn_identity() {
+        let forward = DynAction::new("step/forward", Step::Forward);
+        let also_forward = DynAction::new("step/forward", Leap(5));
+        let leap = DynAction::new("leap", Leap(5));
+        assert_eq!(forward, also_forward);
+        assert_ne!(forward, leap);
+        assert_eq!(format!("{leap:?}"), "DynAction(\"leap\")");
+
+        assert_eq!(forward.downcast_ref::<Step>(), Some(&Step::Forward));
+        assert!(forward.downcast_ref::<Leap>().is_none());
+        assert_eq!(leap.clone().downcast_ref::<Leap>().unwrap().0, 5);
+
+        let set: HashSet<_> = [forward, also_forward, leap].into_iter().collect();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn dyn_agent() {
+        let mut agent = Agent::new(
+            0,
+            vec![
+                DynAction::new("step/forward", Step::Forward),
+                DynAction::new("step/back", Step::Back),
+            ],
+            vec![DynGoal::new(
+                "reach 7",
+                FnGoal::new("7", |n: &i32| *n == 7, |n| (7 - n).abs()),
+            )],
+        );
+
+        // Actions registered later are planned with alongside the others
+        let (_, plan, cost) = agent.plan_constant().unwrap();
+        assert_eq!(plan.len(), 7);
+        assert_eq!(cost, 7);
+        agent.actions.push(DynAction::new("leap", Leap(5)));
+        agent.actions.push(DynAction::new(
+            "double",
+            FnAction::new("double", |n: &i32| *n < 5, |n| *n *= 2, |_| 10),
+        ));
+        let (_, plan, cost) = agent.plan_constant().unwrap();
+        let ids: Vec<_> = plan.iter().map(|action| action.id()).collect();
+        assert_eq!(ids.iter().filter(|id| **id == "leap").count(), 1);
+        assert_eq!(ids.len(), 3);
+        assert_eq!(cost, 5);
+
+        agent.goals.push(DynGoal::new(
+            "reach -2",
+            FnGoal::new("-2", |n: &i32| *n == -2, |_| 0),
+        ));
+        let plans = agent.plan_all();
+        let ids: Vec<_> = plans.iter().map(|(goal, _, _)| goal.id()).collect();
+        assert_eq!(ids, vec!["reach 7", "reach -2"]);
+        assert_eq!(plans[1].1.len(), 2);
+    }
+}
@@ -0,0 +1,68 @@
+use std::error::Error;
+use std::fmt;
+
+/// Diagnostics collected by a search which stopped without finding a plan.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchStats<S> {
+    /// The number of states expanded by the search.
+    pub expanded: usize,
+    /// The number of states still waiting to be expanded when the search stopped.
+    pub frontier: usize,
+    /// The state reached by the longest sequence of actions.
+    pub deepest_state: S,
+    /// The number of actions leading to `deepest_state`.
+    pub max_depth: usize,
+}
+
+/// The reason a plan could not be found.
+///
+/// Returned by the `try_` planning functions, which distinguish the cases that `plan` reports as `None`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PlanError<S> {
+    /// The goal is already satisfied by the initial state, so there is nothing to plan.
+    AlreadySatisfied,
+    /// None of the actions are applicable to the initial state.
+    NoApplicableActions,
+    /// Every reachable state was searched without satisfying the goal.
+    Exhausted(SearchStats<S>),
+    /// The search budget ran out before a plan was found, although one may exist.
+    BudgetExhausted(SearchStats<S>),
+    /// The search was cancelled before it finished.
+    Cancelled,
+}
+
+impl<S> PlanError<S> {
+    /// Returns the search diagnostics, if a search was performed.
+    pub fn stats(&self) -> Option<&SearchStats<S>> {
+        match self {
+            PlanError::Exhausted(stats) | PlanError::BudgetExhausted(stats) => Some(stats),
+            PlanError::AlreadySatisfied | PlanError::NoApplicableActions | PlanError::Cancelled => {
+                None
+            }
+        }
+    }
+}
+
+impl<S> fmt::Display for PlanError<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlanError::AlreadySatisfied => write!(f, "goal is already satisfied"),
+            PlanError::NoApplicableActions => {
+                write!(f, "no actions are applicable to the initial state")
+            }
+            PlanError::Exhausted(stats) => write!(
+                f,
+                "no plan exists after expanding {} states",
+                stats.expanded
+            ),
+            PlanError::BudgetExhausted(stats) => write!(
+                f,
+                "search budget exhausted after expanding {} states with {} left in the frontier",
+                stats.expanded, stats.frontier
+            ),
+            PlanError::Cancelled => write!(f, "search was cancelled"),
+        }
+    }
+}
+
+impl<S: fmt::Debug> Error for PlanError<S> {}
@@ -0,0 +1,582 @@
+use crate::{
+    try_plan, Action, ActionStatus, Agent, Cost, DurativeAction, Goal, Plan, PlanError, PlanStep,
+    SearchBudget,
+};
+use std::hash::Hash;
+
+/// The reason an executor discarded its plan.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Invalidation {
+    /// The agent's state differs from the state the plan expected.
+    Diverged,
+    /// The action of the current step is not applicable to the agent's state.
+    NotApplicable,
+    /// The action of the current step failed while being performed.
+    Failed,
+    /// The plan was discarded by calling `invalidate`.
+    Manual,
+}
+
+/// Something that happened while executing plans, in the order it happened.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExecutorEvent<S, G> {
+    /// A new plan was found for the given goal.
+    PlanFound(G),
+    /// The current plan was discarded, and a new one will be searched for.
+    PlanInvalidated(Invalidation),
+    /// The goal of the current plan was satisfied.
+    PlanCompleted(G),
+    /// No plan could be found.
+    PlanFailed(PlanError<S>),
+}
+
+/// What the agent should be doing, as returned by `Executor::tick`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ExecutorStatus<'a, S, A> {
+    /// The given action should be performed, and `confirm_step` called once the world reflects it.
+    Running(&'a A),
+    /// The goal of the plan was just satisfied.
+    Completed,
+    /// Every goal is already satisfied, so there is nothing to do.
+    Idle,
+    /// No plan could be found.
+    Failed(PlanError<S>),
+}
+
+/// Executes the plans of an agent, checking each step against the agent's state and replanning when needed.
+///
+/// Each call to `tick` returns the action the agent should currently be performing.
+/// The world is responsible for performing it, updating `agent.state`, and calling `confirm_step` once it is done.
+///
+/// Before returning an action, the executor checks it is still applicable to the agent's state,
+/// and that the state matches the state the plan expected at this step.
+/// If either check fails, the plan is discarded and a new one is searched for from the current state.
+///
+/// ## Replanning
+/// If `switch_goals` is true, replanning chooses the goal with the highest priority, like `Agent::try_plan_dynamic`.
+/// Otherwise the executor keeps replanning for the same goal until it is completed or fails.
+///
+/// If `check_divergence` is false, only applicability is checked. This is useful when parts of the state
+/// change continuously and the plan should only be discarded when it can no longer be followed.
+///
+/// ## Durative actions
+/// If the actions implement `DurativeAction`, calling `update` each tick instead of `tick` performs them in a world.
+/// The executor keeps track of the action in progress and confirms its step once it succeeds.
+/// If it fails, the plan is discarded and a new one is searched for from the agent's current state.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+///
+/// #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// struct State {
+///     has_wood: bool,
+///     has_fire: bool,
+/// }
+///
+/// #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// enum MyAction {
+///     ChopWood,
+///     LightFire,
+/// }
+///
+/// impl Action<State> for MyAction {
+///     fn is_applicable(&self, state: &State) -> bool {
+///         match self {
+///             MyAction::ChopWood => !state.has_wood,
+///             MyAction::LightFire => state.has_wood && !state.has_fire,
+///         }
+///     }
+///
+///     fn apply_mut(&self, state: &mut State) {
+///         match self {
+///             MyAction::ChopWood => state.has_wood = true,
+///             MyAction::LightFire => state.has_fire = true,
+///         }
+///     }
+/// }
+///
+/// #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// struct Warm;
+///
+/// impl Goal<State> for Warm {
+///     fn is_satisfied(&self, state: &State) -> bool {
+///         state.has_fire
+///     }
+/// }
+///
+/// let agent = Agent::new(
+///     State { has_wood: false, has_fire: false },
+///     vec![MyAction::ChopWood, MyAction::LightFire],
+///     vec![Warm],
+/// );
+/// let mut executor = Executor::new(agent);
+///
+/// assert_eq!(executor.tick(), ExecutorStatus::Running(&MyAction::ChopWood));
+/// executor.agent.state.has_wood = true;
+/// executor.confirm_step();
+///
+/// // The wood was stolen, so the plan is no longer valid
+/// executor.agent.state.has_wood = false;
+/// assert_eq!(executor.tick(), ExecutorStatus::Running(&MyAction::ChopWood));
+///
+/// executor.agent.state.has_wood = true;
+/// executor.confirm_step();
+/// assert_eq!(executor.tick(), ExecutorStatus::Running(&MyAction::LightFire));
+/// executor.agent.state.has_fire = true;
+/// executor.confirm_step();
+/// assert_eq!(executor.tick(), ExecutorStatus::Completed);
+/// assert_eq!(executor.tick(), ExecutorStatus::Idle);
+///
+/// let events = executor.drain_events();
+/// assert_eq!(
+///     events,
+///     vec![
+///         ExecutorEvent::PlanFound(Warm),
+///         ExecutorEvent::PlanInvalidated(Invalidation::Diverged),
+///         ExecutorEvent::PlanFound(Warm),
+///         ExecutorEvent::PlanCompleted(Warm),
+///     ]
+/// );
+/// ```
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Component))]
+#[derive(Clone, Debug)]
+pub struct Executor<S, A, G, C = i32>
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    G: Goal<S, C> + Clone,
+    C: Cost,
+{
+    pub agent: Agent<S, A, G>,
+    /// The budget for each search. A deadline is an absolute point in time, so it applies to all searches.
+    pub budget: SearchBudget<C>,
+    /// Whether replanning may choose a different goal.
+    pub switch_goals: bool,
+    /// Whether the agent's state must match the state expected by the plan.
+    pub check_divergence: bool,
+    goal: Option<G>,
+    plan: Option<Plan<S, A, C>>,
+    step: usize,
+    running: Option<A>,
+    aborted: Option<A>,
+    events: Vec<ExecutorEvent<S, G>>,
+}
+
+impl<S, A, G, C> Executor<S, A, G, C>
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    G: Goal<S, C> + Clone,
+    C: Cost,
+{
+    /// Creates an executor for the given agent, which switches goals and checks for divergence when replanning.
+    pub fn new(agent: Agent<S, A, G>) -> Self {
+        Self {
+            agent,
+            budget: SearchBudget::unlimited(),
+            switch_goals: true,
+            check_divergence: true,
+            goal: None,
+            plan: None,
+            step: 0,
+            running: None,
+            aborted: None,
+            events: Vec::new(),
+        }
+    }
+
+    /// Returns the goal being pursued, if any.
+    pub fn goal(&self) -> Option<&G> {
+        self.goal.as_ref()
+    }
+
+    /// Returns the plan being executed, if any.
+    pub fn plan(&self) -> Option<&Plan<S, A, C>> {
+        self.plan.as_ref()
+    }
+
+    /// Returns the index of the current step in the plan.
+    pub fn step_index(&self) -> usize {
+        self.step
+    }
+
+    /// Returns the step being executed, if any.
+    pub fn current_step(&self) -> Option<&PlanStep<S, A, C>> {
+        self.plan.as_ref()?.steps().get(self.step)
+    }
+
+    /// Returns the durative action currently being performed by `update`, if any.
+    pub fn running_action(&self) -> Option<&A> {
+        self.running.as_ref()
+    }
+
+    /// Marks the current step as done, so the next tick moves on to the following step.
+    pub fn confirm_step(&mut self) {
+        if self.plan.is_some() {
+            self.step += 1;
+        }
+    }
+
+    /// Discards the current plan, so the next tick searches for a new one.
+    ///
+    /// A durative action in progress is aborted by the next call to `update`.
+    pub fn invalidate(&mut self) {
+        self.discard(Invalidation::Manual);
+    }
+
+    /// Returns and clears the events recorded since the last call.
+    pub fn drain_events(&mut self) -> Vec<ExecutorEvent<S, G>> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Checks the current plan against the agent's state, replans if needed, and returns what to do.
+    pub fn tick(&mut self) -> ExecutorStatus<'_, S, A> {
+        if let Some(goal) = &self.goal {
+            if goal.is_satisfied(&self.agent.state) {
+                let goal = self.goal.take().unwrap();
+                self.plan = None;
+                self.events.push(ExecutorEvent::PlanCompleted(goal));
+                return ExecutorStatus::Completed;
+            }
+        }
+
+        if let Some(invalidation) = self.check() {
+            self.discard(invalidation);
+        }
+        if self.plan.is_none() {
+            match self.replan() {
+                Ok(()) => {}
+                Err(PlanError::AlreadySatisfied) => return ExecutorStatus::Idle,
+                Err(error) => return ExecutorStatus::Failed(error),
+            }
+        }
+
+        match self.current_step() {
+            Some(step) => ExecutorStatus::Running(&step.action),
+            None => unreachable!("a new plan always has a first step"),
+        }
+    }
+
+    /// Performs the plan's durative actions in the given world, replanning if they fail, and returns what is being done.
+    ///
+    /// While an action is running it is ticked, and its preconditions are not checked again.
+    /// Once it succeeds its step is confirmed, and the next action is checked and started as in `tick`.
+    ///
+    /// # Example
+    /// ```
+    /// # use planning::*;
+    ///
+    /// #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    /// struct State {
+    ///     has_wood: bool,
+    /// }
+    ///
+    /// struct World {
+    ///     chops_left: u32,
+    ///     axe_breaks: bool,
+    /// }
+    ///
+    /// #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    /// struct ChopWood;
+    ///
+    /// impl Action<State> for ChopWood {
+    ///     fn is_applicable(&self, state: &State) -> bool {
+    ///         !state.has_wood
+    ///     }
+    ///
+    ///     fn apply_mut(&self, state: &mut State) {
+    ///         state.has_wood = true;
+    ///     }
+    /// }
+    ///
+    /// impl DurativeAction<State, World> for ChopWood {
+    ///     fn start(&self, _state: &mut State, world: &mut World) {
+    ///         world.chops_left = 2;
+    ///     }
+    ///
+    ///     fn tick(&self, state: &mut State, world: &mut World) -> ActionStatus {
+    ///         if world.axe_breaks {
+    ///             world.axe_breaks = false;
+    ///             return ActionStatus::Failed;
+    ///         }
+    ///         world.chops_left -= 1;
+    ///         state.has_wood = world.chops_left == 0;
+    ///         match state.has_wood {
+    ///             true => ActionStatus::Succeeded,
+    ///             false => ActionStatus::Running,
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    /// struct HaveWood;
+    ///
+    /// impl Goal<State> for HaveWood {
+    ///     fn is_satisfied(&self, state: &State) -> bool {
+    ///         state.has_wood
+    ///     }
+    /// }
+    ///
+    /// let agent = Agent::new(State { has_wood: false }, vec![ChopWood], vec![HaveWood]);
+    /// let mut executor = Executor::new(agent);
+    /// let mut world = World { chops_left: 0, axe_breaks: false };
+    ///
+    /// assert_eq!(executor.update(&mut world), ExecutorStatus::Running(&ChopWood));
+    /// assert_eq!(executor.update(&mut world), ExecutorStatus::Running(&ChopWood));
+    ///
+    /// // The action fails part way, so the agent replans and starts again
+    /// world.axe_breaks = true;
+    /// assert_eq!(executor.update(&mut world), ExecutorStatus::Running(&ChopWood));
+    /// assert_eq!(world.chops_left, 2);
+    ///
+    /// assert_eq!(executor.update(&mut world), ExecutorStatus::Running(&ChopWood));
+    /// assert_eq!(executor.update(&mut world), ExecutorStatus::Completed);
+    /// ```
+    pub fn update<W>(&mut self, world: &mut W) -> ExecutorStatus<'_, S, A>
+    where
+        A: DurativeAction<S, W, C>,
+    {
+        if let Some(action) = self.aborted.take() {
+            action.abort(&mut self.agent.state, world);
+        }
+
+        if let Some(action) = &self.running {
+            match action.tick(&mut self.agent.state, world) {
+                ActionStatus::Running => {
+                    return ExecutorStatus::Running(self.running.as_ref().unwrap());
+                }
+                ActionStatus::Succeeded => {
+                    self.running = None;
+                    self.confirm_step();
+                }
+                ActionStatus::Failed => {
+                    self.running = None;
+                    self.discard(Invalidation::Failed);
+                }
+            }
+        }
+
+        let action = match self.tick() {
+            ExecutorStatus::Running(action) => action.clone(),
+            ExecutorStatus::Completed => return ExecutorStatus::Completed,
+            ExecutorStatus::Idle => return ExecutorStatus::Idle,
+            ExecutorStatus::Failed(error) => return ExecutorStatus::Failed(error),
+        };
+        action.start(&mut self.agent.state, world);
+        ExecutorStatus::Running(self.running.insert(action))
+    }
+
+    /// Returns the reason the current plan can't be followed, if any.
+    fn check(&self) -> Option<Invalidation> {
+        let plan = self.plan.as_ref()?;
+        let Some(step) = plan.steps().get(self.step) else {
+            // Every step was performed without satisfying the goal
+            return Some(Invalidation::Diverged);
+        };
+        if self.check_divergence && step.pre_state != self.agent.state {
+            Some(Invalidation::Diverged)
+        } else if !step.action.is_applicable(&self.agent.state) {
+            Some(Invalidation::NotApplicable)
+        } else {
+            None
+        }
+    }
+
+    /// Discards the current plan, keeping its goal unless goals may be switched.
+    fn discard(&mut self, invalidation: Invalidation) {
+        if let Some(action) = self.running.take() {
+            self.aborted = Some(action);
+        }
+        if self.plan.take().is_some() {
+            self.events
+                .push(ExecutorEvent::PlanInvalidated(invalidation));
+        }
+        if self.switch_goals {
+            self.goal = None;
+        }
+    }
+
+    /// Searches for a new plan from the agent's current state.
+    fn replan(&mut self) -> Result<(), PlanError<S>> {
+        let result = match &self.goal {
+            Some(goal) => try_plan(&self.agent.state, &self.agent.actions, goal, &self.budget)
+                .map(|plan| (goal.clone(), plan)),
+            None => self
+                .agent
+                .try_plan_dynamic(&self.budget)
+                .map(|(goal, plan)| (goal.clone(), plan)),
+        };
+        match result {
+            Ok((goal, plan)) => {
+                self.events.push(ExecutorEvent::PlanFound(goal.clone()));
+                self.goal = Some(goal);
+                self.plan = Some(plan);
+                self.step = 0;
+                Ok(())
+            }
+            Err(error) => {
+                self.goal = None;
+                if error != PlanError::AlreadySatisfied {
+                    self.events.push(ExecutorEvent::PlanFailed(error.clone()));
+                }
+                Err(error)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    struct State {
+        position: i32,
+        blocked: bool,
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    enum Move {
+        Left,
+        Right,
+    }
+
+    impl Action<State> for Move {
+        fn is_applicable(&self, state: &State) -> bool {
+            !state.blocked
+        }
+
+        fn apply_mut(&self, state: &mut State) {
+            match self {
+                Move::Left => state.position -= 1,
+                Move::Right => state.position += 1,
+            }
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    struct Reach(i32);
+
+    impl Goal<State> for Reach {
+        fn is_satisfied(&self, state: &State) -> bool {
+            state.position == self.0
+        }
+
+        fn priority(&self, _state: &State) -> i32 {
+            self.0
+        }
+    }
+
+    fn executor(goals: Vec<Reach>) -> Executor<State, Move, Reach> {
+        let state = State {
+            position: 0,
+            blocked: false,
+        };
+        Executor::new(Agent::new(state, vec![Move::Left, Move::Right], goals))
+    }
+
+    #[test]
+    fn executor_not_applicable() {
+        let mut executor = executor(vec![Reach(2)]);
+        executor.check_divergence = false;
+        assert_eq!(executor.tick(), ExecutorStatus::Running(&Move::Right));
+
+        // Divergence is ignored while the action can still be performed
+        executor.agent.state.position = 1;
+        assert_eq!(executor.tick(), ExecutorStatus::Running(&Move::Right));
+        assert_eq!(executor.step_index(), 0);
+
+        executor.agent.state.blocked = true;
+        assert!(matches!(
+            executor.tick(),
+            ExecutorStatus::Failed(PlanError::NoApplicableActions)
+        ));
+        assert_eq!(
+            executor.drain_events(),
+            vec![
+                ExecutorEvent::PlanFound(Reach(2)),
+                ExecutorEvent::PlanInvalidated(Invalidation::NotApplicable),
+                ExecutorEvent::PlanFailed(PlanError::NoApplicableActions),
+            ]
+        );
+    }
+
+    #[test]
+    fn executor_switch_goals() {
+        let mut executor = executor(vec![Reach(2), Reach(-1)]);
+        executor.switch_goals = false;
+        assert_eq!(executor.tick(), ExecutorStatus::Running(&Move::Right));
+        assert_eq!(executor.goal(), Some(&Reach(2)));
+
+        // Replanning keeps the goal, even though another now has a higher priority
+        executor.agent.goals = vec![Reach(3), Reach(2)];
+        executor.invalidate();
+        assert_eq!(executor.tick(), ExecutorStatus::Running(&Move::Right));
+        assert_eq!(executor.goal(), Some(&Reach(2)));
+
+        executor.switch_goals = true;
+        executor.invalidate();
+        assert_eq!(executor.tick(), ExecutorStatus::Running(&Move::Right));
+        assert_eq!(executor.goal(), Some(&Reach(3)));
+        assert_eq!(executor.plan().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn executor_early_completion() {
+        let mut executor = executor(vec![Reach(3)]);
+        assert_eq!(executor.tick(), ExecutorStatus::Running(&Move::Right));
+        executor.agent.state.position = 3;
+        assert_eq!(executor.tick(), ExecutorStatus::Completed);
+        assert_eq!(executor.plan(), None);
+        assert_eq!(executor.tick(), ExecutorStatus::Idle);
+    }
+
+    impl DurativeAction<State, Vec<&'static str>> for Move {
+        fn start(&self, _state: &mut State, log: &mut Vec<&'static str>) {
+            log.push("start");
+        }
+
+        fn tick(&self, state: &mut State, log: &mut Vec<&'static str>) -> ActionStatus {
+            log.push("tick");
+            self.apply_mut(state);
+            ActionStatus::Succeeded
+        }
+
+        fn abort(&self, _state: &mut State, log: &mut Vec<&'static str>) {
+            log.push("abort");
+        }
+    }
+
+    #[test]
+    fn executor_durative() {
+        let mut executor = executor(vec![Reach(2)]);
+        let mut log = Vec::new();
+        assert_eq!(
+            executor.update(&mut log),
+            ExecutorStatus::Running(&Move::Right)
+        );
+        assert_eq!(executor.running_action(), Some(&Move::Right));
+        assert_eq!(
+            executor.update(&mut log),
+            ExecutorStatus::Running(&Move::Right)
+        );
+        assert_eq!(executor.step_index(), 1);
+        assert_eq!(log, vec!["start", "tick", "start"]);
+
+        // Invalidating the plan aborts the running action before replanning
+        executor.invalidate();
+        assert_eq!(executor.running_action(), None);
+        assert_eq!(
+            executor.update(&mut log),
+            ExecutorStatus::Running(&Move::Right)
+        );
+        assert_eq!(executor.step_index(), 0);
+        assert_eq!(executor.update(&mut log), ExecutorStatus::Completed);
+        assert_eq!(
+            log,
+            vec!["start", "tick", "start", "abort", "start", "tick"]
+        );
+        assert_eq!(executor.agent.state.position, 2);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::Cost;
 use std::hash::Hash;
 
 /// Defines a goal that can be satisfied by a state.
@@ -12,9 +13,14 @@ use std::hash::Hash;
 /// ## Heuristic
 /// Implementing the `heuristic` method is optional, and it will default to a constant value if not implemented.
 /// This method will make the search more efficient, and should not *overestimate* the actual cost.
-pub trait Goal<S>
+///
+/// ## Cost
+/// Heuristics and priorities use the same type as the costs of actions, which is `i32` by default.
+/// Goals used with actions implementing `Action<S, C>` should implement `Goal<S, C>`.
+pub trait Goal<S, C = i32>
 where
     S: Clone + Hash + Eq,
+    C: Cost,
 {
     /// Returns true if the goal is satisfied in the given state.
     fn is_satisfied(&self, state: &S) -> bool;
@@ -23,8 +29,8 @@ where
     /// The default implementation returns a constant, and implementing this will make the search more efficient.
     ///
     /// The heuristic should not *overestimate* the actual cost, or else resulting plans may be incorrect.
-    fn heuristic(&self, _state: &S) -> i32 {
-        0
+    fn heuristic(&self, _state: &S) -> C {
+        C::ZERO
     }
 
     /// Returns a priority for this goal based on the given state.
@@ -70,7 +76,7 @@ where
     /// assert_eq!(goal.priority(&state), 2);
     ///
     /// ```
-    fn priority(&self, _state: &S) -> i32 {
-        0
+    fn priority(&self, _state: &S) -> C {
+        C::ZERO
     }
 }
@@ -0,0 +1,452 @@
+use crate::{check_initial, Action, Cost, Goal, Plan, PlanError, SearchBudget, SearchStats};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::Hash;
+
+/// Options for finding several plans with `try_plan_k_best`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KBestOptions {
+    /// The maximum number of plans to find.
+    pub k: usize,
+    /// The minimum number of actions which must be inserted, removed or replaced
+    /// to turn any of the plans into another.
+    pub min_distance: usize,
+    /// The maximum number of times each state may be expanded.
+    ///
+    /// Each expansion continues a different plan through the state, so this limits the search for alternatives.
+    /// It defaults to `k`, which is enough to find the `k` cheapest plans in most cases.
+    /// With a `min_distance`, many plans may be rejected, so a higher limit can find plans that would otherwise be missed.
+    pub max_state_expansions: usize,
+}
+
+impl KBestOptions {
+    /// Returns options to find the `k` cheapest plans, without any diversity constraint.
+    pub fn new(k: usize) -> Self {
+        Self {
+            k,
+            min_distance: 0,
+            max_state_expansions: k,
+        }
+    }
+
+    /// Requires every plan to differ from the others by at least the given number of actions.
+    pub fn with_min_distance(mut self, min_distance: usize) -> Self {
+        self.min_distance = min_distance;
+        self
+    }
+
+    /// Limits the number of times each state may be expanded.
+    pub fn with_max_state_expansions(mut self, max_state_expansions: usize) -> Self {
+        self.max_state_expansions = max_state_expansions;
+        self
+    }
+}
+
+/// A partial plan in the search, linked to the partial plan it continues.
+struct PathNode<S, A, C> {
+    state: S,
+    parent: Option<(usize, A)>,
+    cost: C,
+    depth: usize,
+}
+
+/// Returns up to `k` of the cheapest plans to satisfy the goal, with their total costs, in order of cost.
+///
+/// Plans never visit the same state twice, and stop as soon as the goal is satisfied.
+/// Fewer than `k` plans are returned if there aren't enough of them,
+/// although the search only ends once every reachable state was searched.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+///
+/// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+/// struct Counter(u32);
+///
+/// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+/// enum Add {
+///     One,
+///     Two,
+/// }
+///
+/// impl Action<Counter> for Add {
+///     fn is_applicable(&self, state: &Counter) -> bool {
+///         state.0 < 3
+///     }
+///
+///     fn apply_mut(&self, state: &mut Counter) {
+///         state.0 += match self {
+///             Add::One => 1,
+///             Add::Two => 2,
+///         };
+///     }
+/// }
+///
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// struct Reach(u32);
+///
+/// impl Goal<Counter> for Reach {
+///     fn is_satisfied(&self, state: &Counter) -> bool {
+///         state.0 == self.0
+///     }
+/// }
+///
+/// let plans = plan_k_best(&Counter(0), &[Add::One, Add::Two], &Reach(3), 5);
+/// assert_eq!(
+///     plans,
+///     vec![
+///         (vec![Add::One, Add::Two], 2),
+///         (vec![Add::Two, Add::One], 2),
+///         (vec![Add::One, Add::One, Add::One], 3),
+///     ]
+/// );
+/// ```
+pub fn plan_k_best<S, A, G, C>(
+    initial_state: &S,
+    actions: &[A],
+    goal: &G,
+    k: usize,
+) -> Vec<(Vec<A>, C)>
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    G: Goal<S, C> + Clone,
+    C: Cost,
+{
+    let options = KBestOptions::new(k);
+    match try_plan_k_best(
+        initial_state,
+        actions,
+        goal,
+        &options,
+        &SearchBudget::unlimited(),
+    ) {
+        Ok(plans) => plans
+            .into_iter()
+            .map(|plan| {
+                let cost = plan.cost();
+                (plan.into_actions(), cost)
+            })
+            .collect(),
+        Err(PlanError::AlreadySatisfied) if k > 0 => vec![(vec![], C::ZERO)],
+        Err(_) => vec![],
+    }
+}
+
+/// Returns up to `options.k` of the cheapest plans to satisfy the goal within the budget, in order of cost,
+/// or the reason none were found.
+///
+/// Plans never visit the same state twice, and stop as soon as the goal is satisfied.
+/// If `options.min_distance` is set, each plan is only kept if it differs enough from every cheaper plan which was kept.
+///
+/// If the budget runs out after some plans were found, those plans are returned.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+///
+/// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+/// struct Position(i32, i32);
+///
+/// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+/// enum Move {
+///     Right,
+///     Up,
+/// }
+///
+/// impl Action<Position> for Move {
+///     fn is_applicable(&self, state: &Position) -> bool {
+///         match self {
+///             Move::Right => state.0 < 2,
+///             Move::Up => state.1 < 2,
+///         }
+///     }
+///
+///     fn apply_mut(&self, state: &mut Position) {
+///         match self {
+///             Move::Right => state.0 += 1,
+///             Move::Up => state.1 += 1,
+///         }
+///     }
+/// }
+///
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// struct Reach(i32, i32);
+///
+/// impl Goal<Position> for Reach {
+///     fn is_satisfied(&self, state: &Position) -> bool {
+///         state.0 == self.0 && state.1 == self.1
+///     }
+/// }
+///
+/// let actions = [Move::Right, Move::Up];
+/// let budget = SearchBudget::unlimited();
+///
+/// // There are 6 ways to move 2 steps right and 2 steps up, but only 2 differ in every action
+/// let options = KBestOptions::new(6).with_min_distance(4).with_max_state_expansions(6);
+/// let plans = try_plan_k_best(&Position(0, 0), &actions, &Reach(2, 2), &options, &budget).unwrap();
+/// let plans: Vec<_> = plans.into_iter().map(|plan| plan.into_actions()).collect();
+/// assert_eq!(
+///     plans,
+///     vec![
+///         vec![Move::Right, Move::Right, Move::Up, Move::Up],
+///         vec![Move::Up, Move::Up, Move::Right, Move::Right],
+///     ]
+/// );
+/// ```
+pub fn try_plan_k_best<S, A, G, C>(
+    initial_state: &S,
+    actions: &[A],
+    goal: &G,
+    options: &KBestOptions,
+    budget: &SearchBudget<C>,
+) -> Result<Vec<Plan<S, A, C>>, PlanError<S>>
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    G: Goal<S, C>,
+    C: Cost,
+{
+    if let Some(error) = check_initial(initial_state, actions, goal) {
+        return Err(error);
+    }
+    if options.k == 0 {
+        return Ok(vec![]);
+    }
+
+    let mut nodes = vec![PathNode {
+        state: initial_state.clone(),
+        parent: None,
+        cost: C::ZERO,
+        depth: 0,
+    }];
+    let mut open = BinaryHeap::from([Reverse((goal.heuristic(initial_state), 0))]);
+    let mut expansions: HashMap<S, usize> = HashMap::new();
+    let mut plans: Vec<Vec<A>> = Vec::new();
+    let mut expanded = 0;
+    let mut deepest = 0;
+    let mut pruned = false;
+    let mut budget_exhausted = false;
+
+    while plans.len() < options.k {
+        let Some(Reverse((estimate, index))) = open.pop() else {
+            break;
+        };
+        let node = &nodes[index];
+        if goal.is_satisfied(&node.state) {
+            let plan = path(&nodes, index);
+            if plans
+                .iter()
+                .all(|other| edit_distance(other, &plan) >= options.min_distance)
+            {
+                plans.push(plan);
+            }
+            continue;
+        }
+
+        let count = expansions.entry(node.state.clone()).or_insert(0);
+        if *count >= options.max_state_expansions {
+            pruned = true;
+            continue;
+        }
+        if budget.is_spent(expanded) {
+            open.push(Reverse((estimate, index)));
+            budget_exhausted = true;
+            break;
+        }
+        *count += 1;
+        expanded += 1;
+
+        let (current, current_cost, depth) = (node.state.clone(), node.cost, node.depth + 1);
+        for action in actions.iter().filter(|a| a.is_applicable(&current)) {
+            let state = action.apply(&current);
+            let cost = current_cost + action.cost(&current);
+            if !budget.allows(depth, cost) {
+                pruned = true;
+                continue;
+            }
+            if on_path(&nodes, index, &state) {
+                continue;
+            }
+            let estimate = cost + goal.heuristic(&state);
+            open.push(Reverse((estimate, nodes.len())));
+            nodes.push(PathNode {
+                state,
+                parent: Some((index, action.clone())),
+                cost,
+                depth,
+            });
+            if depth > nodes[deepest].depth {
+                deepest = nodes.len() - 1;
+            }
+        }
+    }
+
+    if plans.is_empty() {
+        let stats = SearchStats {
+            expanded,
+            frontier: open.len(),
+            deepest_state: nodes[deepest].state.clone(),
+            max_depth: nodes[deepest].depth,
+        };
+        return Err(if budget_exhausted || pruned {
+            PlanError::BudgetExhausted(stats)
+        } else {
+            PlanError::Exhausted(stats)
+        });
+    }
+    Ok(plans
+        .into_iter()
+        .map(|actions| Plan::from_actions(initial_state, actions))
+        .collect())
+}
+
+/// Returns the actions leading to the node at the given index.
+fn path<S, A: Clone, C>(nodes: &[PathNode<S, A, C>], index: usize) -> Vec<A> {
+    let mut path = Vec::new();
+    let mut current = index;
+    while let Some((parent, action)) = &nodes[current].parent {
+        path.push(action.clone());
+        current = *parent;
+    }
+    path.reverse();
+    path
+}
+
+/// Returns true if the state was visited by the partial plan ending at the given index.
+fn on_path<S: Eq, A, C>(nodes: &[PathNode<S, A, C>], index: usize, state: &S) -> bool {
+    let mut current = Some(index);
+    while let Some(index) = current {
+        if &nodes[index].state == state {
+            return true;
+        }
+        current = nodes[index].parent.as_ref().map(|(parent, _)| *parent);
+    }
+    false
+}
+
+/// Returns the number of actions which must be inserted, removed or replaced to turn one plan into the other.
+fn edit_distance<A: Eq>(a: &[A], b: &[A]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, x) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, y) in b.iter().enumerate() {
+            let replace = previous[j] + usize::from(x != y);
+            current[j + 1] = replace.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    struct Position(i32, i32);
+
+    #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    enum Move {
+        Left,
+        Right,
+        Up,
+        Diagonal,
+    }
+
+    impl Action<Position> for Move {
+        fn is_applicable(&self, state: &Position) -> bool {
+            match self {
+                Move::Left => state.0 > 0,
+                _ => state.0 < 3 && state.1 < 3,
+            }
+        }
+
+        fn apply_mut(&self, state: &mut Position) {
+            match self {
+                Move::Left => state.0 -= 1,
+                Move::Right => state.0 += 1,
+                Move::Up => state.1 += 1,
+                Move::Diagonal => {
+                    state.0 += 1;
+                    state.1 += 1;
+                }
+            }
+        }
+
+        fn cost(&self, _state: &Position) -> i32 {
+            match self {
+                Move::Diagonal => 3,
+                _ => 1,
+            }
+        }
+    }
+
+    #[derive(PartialEq, Eq, Hash, Clone)]
+    struct Reach(i32, i32);
+
+    impl Goal<Position> for Reach {
+        fn is_satisfied(&self, state: &Position) -> bool {
+            state.0 == self.0 && state.1 == self.1
+        }
+    }
+
+    #[test]
+    fn k_best_order() {
+        use Move::*;
+        let actions = [Left, Right, Up, Diagonal];
+        let plans = plan_k_best(&Position(0, 0), &actions, &Reach(1, 1), 4);
+        assert_eq!(
+            plans,
+            vec![
+                (vec![Right, Up], 2),
+                (vec![Up, Right], 2),
+                (vec![Diagonal], 3),
+                (vec![Right, Right, Up, Left], 4),
+            ]
+        );
+
+        assert_eq!(
+            plan_k_best(&Position(0, 0), &actions, &Reach(1, 1), 0),
+            vec![]
+        );
+        assert_eq!(
+            plan_k_best(&Position(1, 1), &actions, &Reach(1, 1), 2),
+            vec![(vec![], 0)]
+        );
+    }
+
+    #[test]
+    fn k_best_diverse() {
+        let actions = [Move::Left, Move::Right, Move::Up, Move::Diagonal];
+        let budget = SearchBudget::unlimited();
+        let options = KBestOptions::new(5)
+            .with_min_distance(2)
+            .with_max_state_expansions(20);
+        let plans =
+            try_plan_k_best(&Position(0, 0), &actions, &Reach(2, 2), &options, &budget).unwrap();
+        assert_eq!(plans.len(), 5);
+        for (i, plan) in plans.iter().enumerate() {
+            assert_eq!(plan.final_state(), Some(&Position(2, 2)));
+            for other in &plans[..i] {
+                assert!(other.cost() <= plan.cost());
+                let (a, b): (Vec<_>, Vec<_>) =
+                    (other.actions().collect(), plan.actions().collect());
+                assert!(edit_distance(&a, &b) >= 2);
+            }
+        }
+
+        let budget = budget.with_max_expanded(1);
+        let result = try_plan_k_best(&Position(0, 0), &actions, &Reach(2, 2), &options, &budget);
+        assert!(matches!(result, Err(PlanError::BudgetExhausted(_))));
+    }
+
+    #[test]
+    fn k_best_edit_distance() {
+        assert_eq!(edit_distance::<i32>(&[], &[]), 0);
+        assert_eq!(edit_distance(&[1, 2, 3], &[]), 3);
+        assert_eq!(edit_distance(&[1, 2, 3], &[1, 3]), 1);
+        assert_eq!(edit_distance(&[1, 2, 3], &[3, 2, 1]), 2);
+        assert_eq!(edit_distance(&[1, 1, 2, 2], &[2, 2, 1, 1]), 4);
+    }
+}
@@ -82,9 +82,62 @@
 
 mod action;
 mod agent;
+mod anytime;
+mod budget;
+mod check;
+mod closure;
+mod cost;
+#[cfg(feature = "serde")]
+mod definition;
+mod dynamic;
+mod error;
+mod executor;
 mod goal;
+mod kbest;
+mod lifted;
+#[cfg(feature = "pddl")]
+mod pddl;
 mod plan;
+#[cfg(feature = "bevy")]
+mod plugin;
+mod regress;
+mod relaxed;
+#[cfg(feature = "bevy")]
+mod sensor;
+mod strategy;
+mod strips;
+mod successor;
+mod task;
+mod world;
 pub use action::*;
 pub use agent::*;
+pub use anytime::*;
+pub use budget::*;
+pub use check::*;
+pub use closure::*;
+pub use cost::*;
+#[cfg(feature = "serde")]
+pub use definition::*;
+pub use dynamic::*;
+pub use error::*;
+pub use executor::*;
 pub use goal::*;
+pub use kbest::*;
+pub use lifted::*;
+#[cfg(feature = "pddl")]
+pub use pddl::*;
 pub use plan::*;
+#[cfg(feature = "bevy")]
+pub use plugin::*;
+pub use regress::*;
+pub use relaxed::*;
+#[cfg(feature = "bevy")]
+pub use sensor::*;
+pub use strategy::*;
+pub use strips::*;
+pub use successor::*;
+pub use task::*;
+pub use world::*;
+
+#[cfg(feature = "derive")]
+pub use planning_derive::{Action, Goal};
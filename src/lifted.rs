@@ -0,0 +1,232 @@
+use crate::{
+    try_plan_with_successors, Action, Cost, Goal, Plan, PlanError, SearchBudget, SuccessorGenerator,
+};
+use std::hash::Hash;
+
+/// A template for actions which depend on the state, such as moving to each of the points of interest it contains.
+///
+/// Instead of enumerating every concrete action up front, each template generates them
+/// for every state expanded during the search.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+///
+/// type Pos = (i32, i32);
+///
+/// #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// struct State {
+///     position: Pos,
+///     trees: Vec<Pos>,
+///     wood: u32,
+/// }
+///
+/// #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// enum WoodAction {
+///     MoveTo(Pos),
+///     Chop,
+/// }
+///
+/// impl Action<State> for WoodAction {
+///     fn is_applicable(&self, state: &State) -> bool {
+///         match self {
+///             WoodAction::MoveTo(position) => state.position != *position,
+///             WoodAction::Chop => state.trees.contains(&state.position),
+///         }
+///     }
+///
+///     fn apply_mut(&self, state: &mut State) {
+///         match self {
+///             WoodAction::MoveTo(position) => state.position = *position,
+///             WoodAction::Chop => {
+///                 state.trees.retain(|tree| *tree != state.position);
+///                 state.wood += 1;
+///             }
+///         }
+///     }
+///
+///     fn cost(&self, state: &State) -> i32 {
+///         match self {
+///             WoodAction::MoveTo((x, y)) => (x - state.position.0).abs() + (y - state.position.1).abs(),
+///             WoodAction::Chop => 1,
+///         }
+///     }
+/// }
+///
+/// enum Template {
+///     MoveToTree,
+///     Chop,
+/// }
+///
+/// impl LiftedAction<State> for Template {
+///     type Action = WoodAction;
+///
+///     fn ground(&self, state: &State, out: &mut Vec<WoodAction>) {
+///         match self {
+///             Template::MoveToTree => out.extend(state.trees.iter().copied().map(WoodAction::MoveTo)),
+///             Template::Chop => out.push(WoodAction::Chop),
+///         }
+///     }
+/// }
+///
+/// struct Gather(u32);
+///
+/// impl Goal<State> for Gather {
+///     fn is_satisfied(&self, state: &State) -> bool {
+///         state.wood >= self.0
+///     }
+/// }
+///
+/// let state = State {
+///     position: (0, 0),
+///     trees: vec![(2, 0), (5, 0), (0, 1)],
+///     wood: 0,
+/// };
+///
+/// let templates = [Template::MoveToTree, Template::Chop];
+/// let (path, cost) = plan_lifted(&state, &templates, &Gather(2)).unwrap();
+/// assert_eq!(
+///     path,
+///     vec![
+///         WoodAction::MoveTo((0, 1)),
+///         WoodAction::Chop,
+///         WoodAction::MoveTo((2, 0)),
+///         WoodAction::Chop,
+///     ]
+/// );
+/// assert_eq!(cost, 6);
+/// ```
+pub trait LiftedAction<S, C = i32>
+where
+    S: Clone + Hash + Eq,
+    C: Cost,
+{
+    /// The concrete actions generated by the template.
+    type Action: Action<S, C> + Eq + Clone + Hash;
+
+    /// Adds the concrete actions for the given state to `out`.
+    ///
+    /// Actions which aren't applicable to the state are skipped by the search,
+    /// so they don't need to be filtered out here.
+    fn ground(&self, state: &S, out: &mut Vec<Self::Action>);
+}
+
+/// The concrete actions generated by a set of templates.
+struct Groundings<'a, L>(&'a [L]);
+
+impl<S, L, C> SuccessorGenerator<S, L::Action, C> for Groundings<'_, L>
+where
+    S: Clone + Hash + Eq,
+    L: LiftedAction<S, C>,
+    C: Cost,
+{
+    fn applicable(&self, state: &S, out: &mut Vec<L::Action>) {
+        let start = out.len();
+        for template in self.0 {
+            template.ground(state, out);
+        }
+        let grounded = out.split_off(start);
+        out.extend(
+            grounded
+                .into_iter()
+                .filter(|action| action.is_applicable(state)),
+        );
+    }
+}
+
+/// Returns a sequence of actions generated by the templates to reach the goal while minimizing cost.
+///
+/// Returns `None` if no plan exists. See `LiftedAction` for an example.
+pub fn plan_lifted<S, L, G, C>(
+    initial_state: &S,
+    templates: &[L],
+    goal: &G,
+) -> Option<(Vec<L::Action>, C)>
+where
+    S: Clone + Hash + Eq,
+    L: LiftedAction<S, C>,
+    G: Goal<S, C>,
+    C: Cost,
+{
+    match try_plan_lifted(initial_state, templates, goal, &SearchBudget::unlimited()) {
+        Ok(plan) => {
+            let cost = plan.cost();
+            Some((plan.into_actions(), cost))
+        }
+        Err(PlanError::AlreadySatisfied) => Some((vec![], C::ZERO)),
+        Err(_) => None,
+    }
+}
+
+/// Returns a plan made of actions generated by the templates within the budget, or the reason none was found.
+pub fn try_plan_lifted<S, L, G, C>(
+    initial_state: &S,
+    templates: &[L],
+    goal: &G,
+    budget: &SearchBudget<C>,
+) -> Result<Plan<S, L::Action, C>, PlanError<S>>
+where
+    S: Clone + Hash + Eq,
+    L: LiftedAction<S, C>,
+    G: Goal<S, C>,
+    C: Cost,
+{
+    try_plan_with_successors(initial_state, &Groundings(templates), goal, budget)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    struct Jump(u32);
+
+    impl Action<u32> for Jump {
+        fn is_applicable(&self, state: &u32) -> bool {
+            self.0 > *state && self.0 <= 100
+        }
+
+        fn apply_mut(&self, state: &mut u32) {
+            *state = self.0;
+        }
+    }
+
+    /// Jumps to any multiple of the current number, generating some which aren't applicable.
+    struct Multiples(u32);
+
+    impl LiftedAction<u32> for Multiples {
+        type Action = Jump;
+
+        fn ground(&self, state: &u32, out: &mut Vec<Jump>) {
+            out.extend((0..=self.0).map(|factor| Jump(state * factor)));
+        }
+    }
+
+    struct Reach(u32);
+
+    impl Goal<u32> for Reach {
+        fn is_satisfied(&self, state: &u32) -> bool {
+            *state == self.0
+        }
+    }
+
+    #[test]
+    fn lifted_groundings_per_state() {
+        let mut out = vec![Jump(100)];
+        Groundings(&[Multiples(3)][..]).applicable(&2, &mut out);
+        assert_eq!(out, vec![Jump(100), Jump(4), Jump(6)]);
+
+        let (path, cost) = plan_lifted(&1, &[Multiples(3)], &Reach(1)).unwrap();
+        assert!(path.is_empty());
+        assert_eq!(cost, 0);
+
+        // Neither 0 nor 1 are larger than 1
+        let result = try_plan_lifted(&1, &[Multiples(1)], &Reach(4), &SearchBudget::unlimited());
+        assert!(matches!(result, Err(PlanError::NoApplicableActions)));
+
+        let (path, cost) = plan_lifted(&5, &[Multiples(3)], &Reach(45)).unwrap();
+        assert_eq!(path, vec![Jump(15), Jump(45)]);
+        assert_eq!(cost, 2);
+        assert!(plan_lifted(&2, &[Multiples(3)], &Reach(20)).is_none());
+    }
+}
@@ -0,0 +1,1014 @@
+use crate::{FactGoal, RelaxedHeuristic, StripsAction, WorldState};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+
+/// The PDDL file in which a `PddlError` was found.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PddlFile {
+    Domain,
+    Problem,
+}
+
+impl fmt::Display for PddlFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PddlFile::Domain => write!(f, "domain"),
+            PddlFile::Problem => write!(f, "problem"),
+        }
+    }
+}
+
+/// An error in a PDDL domain or problem file, with the line and column at which it was found.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PddlError {
+    /// The file containing the error.
+    pub file: PddlFile,
+    /// The line of the error, starting from 1.
+    pub line: usize,
+    /// The column of the error, starting from 1.
+    pub column: usize,
+    /// A description of what is wrong.
+    pub message: String,
+}
+
+impl fmt::Display for PddlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}: {}",
+            self.file, self.line, self.column, self.message
+        )
+    }
+}
+
+impl Error for PddlError {}
+
+/// A PDDL problem, grounded into `StripsAction`s over a `WorldState`.
+///
+/// Each ground atom of the problem, such as `(at ball1 rooma)`, is a boolean fact with its own key.
+/// Atoms which no action can change are checked while grounding, so they only appear in the state if the goal needs them.
+///
+/// The supported requirements are `:strips`, `:typing`, `:negative-preconditions`, `:equality` and `:action-costs`.
+/// Action costs are given by `(increase (total-cost) n)` effects, and are 1 otherwise.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+///
+/// let domain = "
+///     (define (domain switches)
+///       (:requirements :strips :typing)
+///       (:types switch)
+///       (:predicates (on ?s - switch))
+///       (:action flip-on
+///         :parameters (?s - switch)
+///         :precondition (not (on ?s))
+///         :effect (on ?s)))
+/// ";
+///
+/// let problem = "
+///     (define (problem lights)
+///       (:domain switches)
+///       (:objects hall kitchen - switch)
+///       (:init (on hall))
+///       (:goal (and (on hall) (on kitchen))))
+/// ";
+///
+/// let problem = PddlProblem::parse(domain, problem).unwrap();
+/// let (path, cost) = plan(problem.initial_state(), problem.actions(), problem.goal()).unwrap();
+/// let names: Vec<_> = path.iter().map(|action| action.name()).collect();
+/// assert_eq!(names, vec!["(flip-on kitchen)"]);
+/// assert_eq!(cost, 1);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct PddlProblem {
+    domain_name: String,
+    problem_name: String,
+    facts: Vec<String>,
+    initial_state: WorldState,
+    goal: WorldState,
+    actions: Vec<StripsAction>,
+}
+
+impl PddlProblem {
+    /// Parses and grounds a problem, given the contents of its domain and problem files.
+    pub fn parse(domain: &str, problem: &str) -> Result<Self, PddlError> {
+        let domain = Reader::new(PddlFile::Domain).domain(domain)?;
+        let problem = Reader::new(PddlFile::Problem).problem(problem, &domain)?;
+        Ok(ground(&domain, &problem))
+    }
+
+    /// Returns the name of the domain.
+    pub fn domain_name(&self) -> &str {
+        &self.domain_name
+    }
+
+    /// Returns the name of the problem.
+    pub fn problem_name(&self) -> &str {
+        &self.problem_name
+    }
+
+    /// Returns the state given by the `:init` section, where every atom not listed is false.
+    pub fn initial_state(&self) -> &WorldState {
+        &self.initial_state
+    }
+
+    /// Returns the facts required by the `:goal` section.
+    ///
+    /// This can be used as a goal directly, without a heuristic.
+    pub fn goal(&self) -> &WorldState {
+        &self.goal
+    }
+
+    /// Returns the goal with a heuristic derived from the problem's actions.
+    pub fn fact_goal(&self, heuristic: RelaxedHeuristic) -> FactGoal {
+        FactGoal::new(self.goal.clone(), self.actions.clone()).with_heuristic(heuristic)
+    }
+
+    /// Returns the ground actions, named like `(move rooma roomb)`.
+    pub fn actions(&self) -> &[StripsAction] {
+        &self.actions
+    }
+
+    /// Returns the atom for the given fact key, such as `(at ball1 rooma)`.
+    pub fn fact_name(&self, key: usize) -> Option<&str> {
+        self.facts.get(key).map(String::as_str)
+    }
+
+    /// Returns the fact key for the given atom, such as `(at ball1 rooma)`.
+    pub fn fact_key(&self, name: &str) -> Option<usize> {
+        self.facts.iter().position(|fact| fact == name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Position {
+    line: usize,
+    column: usize,
+}
+
+/// An s-expression, with atoms in lowercase as PDDL is case-insensitive.
+#[derive(Debug)]
+enum Sexp {
+    Atom(String, Position),
+    List(Vec<Sexp>, Position),
+}
+
+impl Sexp {
+    fn position(&self) -> Position {
+        match self {
+            Sexp::Atom(_, position) | Sexp::List(_, position) => *position,
+        }
+    }
+}
+
+/// A predicate applied to variables or objects, which may be negated.
+#[derive(Debug)]
+struct Literal {
+    positive: bool,
+    predicate: String,
+    arguments: Vec<String>,
+    position: Position,
+}
+
+#[derive(Debug)]
+struct Schema {
+    name: String,
+    parameters: Vec<(String, String)>,
+    preconditions: Vec<Literal>,
+    effects: Vec<Literal>,
+    cost: i32,
+}
+
+#[derive(Debug)]
+struct Domain {
+    name: String,
+    /// The parent of each type, apart from `object`.
+    types: HashMap<String, String>,
+    constants: Vec<(String, String)>,
+    predicates: HashMap<String, usize>,
+    actions: Vec<Schema>,
+}
+
+impl Domain {
+    fn has_type(&self, name: &str) -> bool {
+        name == "object" || self.types.contains_key(name)
+    }
+
+    fn is_subtype<'a>(&'a self, mut name: &'a str, of: &str) -> bool {
+        for _ in 0..=self.types.len() {
+            if name == of {
+                return true;
+            }
+            match self.types.get(name) {
+                Some(parent) => name = parent,
+                None => return false,
+            }
+        }
+        false
+    }
+}
+
+#[derive(Debug)]
+struct Problem {
+    name: String,
+    objects: Vec<(String, String)>,
+    init: Vec<Literal>,
+    goal: Vec<Literal>,
+}
+
+const REQUIREMENTS: [&str; 5] = [
+    ":strips",
+    ":typing",
+    ":negative-preconditions",
+    ":equality",
+    ":action-costs",
+];
+
+const UNSUPPORTED: [&str; 6] = ["or", "imply", "exists", "forall", "when", "either"];
+
+struct Reader {
+    file: PddlFile,
+}
+
+impl Reader {
+    fn new(file: PddlFile) -> Self {
+        Self { file }
+    }
+
+    fn error<T>(&self, position: Position, message: impl Into<String>) -> Result<T, PddlError> {
+        Err(PddlError {
+            file: self.file,
+            line: position.line,
+            column: position.column,
+            message: message.into(),
+        })
+    }
+
+    /// Reads the single s-expression making up a file.
+    fn read(&self, source: &str) -> Result<Sexp, PddlError> {
+        let mut stack: Vec<(Vec<Sexp>, Position)> = vec![];
+        let mut top = vec![];
+        let mut chars = source.chars().peekable();
+        let (mut line, mut column) = (1, 1);
+
+        while let Some(&c) = chars.peek() {
+            let position = Position { line, column };
+            if c == '(' || c == ')' || c == ';' || c.is_whitespace() {
+                chars.next();
+                if c == '\n' {
+                    line += 1;
+                    column = 1;
+                } else {
+                    column += 1;
+                }
+            }
+            match c {
+                '(' => stack.push((vec![], position)),
+                ')' => {
+                    let Some((items, start)) = stack.pop() else {
+                        return self.error(position, "unexpected `)`");
+                    };
+                    let list = Sexp::List(items, start);
+                    match stack.last_mut() {
+                        Some((parent, _)) => parent.push(list),
+                        None => top.push(list),
+                    }
+                }
+                ';' => while chars.next_if(|&c| c != '\n').is_some() {},
+                c if c.is_whitespace() => {}
+                _ => {
+                    let mut text = String::new();
+                    while let Some(c) =
+                        chars.next_if(|&c| c != '(' && c != ')' && c != ';' && !c.is_whitespace())
+                    {
+                        text.extend(c.to_lowercase());
+                        column += 1;
+                    }
+                    let atom = Sexp::Atom(text, position);
+                    match stack.last_mut() {
+                        Some((parent, _)) => parent.push(atom),
+                        None => top.push(atom),
+                    }
+                }
+            }
+        }
+
+        if let Some((_, start)) = stack.pop() {
+            return self.error(start, "unclosed `(`");
+        }
+        let mut top = top.into_iter();
+        let Some(definition) = top.next() else {
+            return self.error(Position { line, column }, "expected `(define ...)`");
+        };
+        if let Some(extra) = top.next() {
+            return self.error(
+                extra.position(),
+                "unexpected expression after the definition",
+            );
+        }
+        Ok(definition)
+    }
+
+    fn list<'a>(&self, sexp: &'a Sexp, expected: &str) -> Result<&'a [Sexp], PddlError> {
+        match sexp {
+            Sexp::List(items, _) => Ok(items),
+            Sexp::Atom(..) => self.error(sexp.position(), format!("expected {expected}")),
+        }
+    }
+
+    fn atom<'a>(&self, sexp: &'a Sexp, expected: &str) -> Result<&'a str, PddlError> {
+        match sexp {
+            Sexp::Atom(text, _) => Ok(text),
+            Sexp::List(..) => self.error(sexp.position(), format!("expected {expected}")),
+        }
+    }
+
+    /// Reads `(define (<kind> <name>) sections...)`, returning the name and sections.
+    fn definition<'a>(
+        &self,
+        sexp: &'a Sexp,
+        kind: &str,
+    ) -> Result<(&'a str, &'a [Sexp]), PddlError> {
+        let items = self.list(sexp, "`(define ...)`")?;
+        match items.first() {
+            Some(Sexp::Atom(define, _)) if define == "define" => {}
+            _ => return self.error(sexp.position(), "expected `(define ...)`"),
+        }
+        let Some(header) = items.get(1) else {
+            return self.error(sexp.position(), format!("expected `({kind} <name>)`"));
+        };
+        let name = match self.list(header, &format!("`({kind} <name>)`"))? {
+            [keyword, name] if self.atom(keyword, kind)? == kind => self.atom(name, "a name")?,
+            _ => return self.error(header.position(), format!("expected `({kind} <name>)`")),
+        };
+        Ok((name, &items[2..]))
+    }
+
+    /// Reads a section like `(:keyword items...)`, returning the keyword and items.
+    fn section<'a>(&self, sexp: &'a Sexp) -> Result<(&'a str, &'a [Sexp]), PddlError> {
+        match self.list(sexp, "a section")? {
+            [Sexp::Atom(keyword, _), items @ ..] if keyword.starts_with(':') => {
+                Ok((keyword, items))
+            }
+            _ => self.error(sexp.position(), "expected a section like `(:keyword ...)`"),
+        }
+    }
+
+    /// Reads a list of names, each of which may be followed by `- type`.
+    /// Names without a type have the type `object`.
+    fn typed_list(&self, items: &[Sexp]) -> Result<Vec<(String, String, Position)>, PddlError> {
+        let mut typed = vec![];
+        let mut pending: Vec<(String, Position)> = vec![];
+        let mut items = items.iter();
+        while let Some(item) = items.next() {
+            let name = self.atom(item, "a name")?;
+            if name != "-" {
+                pending.push((name.to_string(), item.position()));
+                continue;
+            }
+            let Some(kind) = items.next() else {
+                return self.error(item.position(), "expected a type after `-`");
+            };
+            if let Sexp::List(..) = kind {
+                return self.error(kind.position(), "`either` types are not supported");
+            }
+            let kind = self.atom(kind, "a type")?;
+            typed.extend(
+                pending
+                    .drain(..)
+                    .map(|(name, position)| (name, kind.to_string(), position)),
+            );
+        }
+        typed.extend(
+            pending
+                .into_iter()
+                .map(|(name, position)| (name, "object".to_string(), position)),
+        );
+        Ok(typed)
+    }
+
+    /// Reads a conjunction of literals, adding any `(increase (total-cost) n)` to the cost if one is given.
+    fn literals(
+        &self,
+        sexp: &Sexp,
+        out: &mut Vec<Literal>,
+        mut cost: Option<&mut i32>,
+    ) -> Result<(), PddlError> {
+        let items = self.list(sexp, "a condition")?;
+        let Some(head) = items.first() else {
+            return Ok(());
+        };
+        let head = self.atom(head, "a predicate")?;
+        match head {
+            "and" => {
+                for item in &items[1..] {
+                    self.literals(item, out, cost.as_deref_mut())?;
+                }
+            }
+            "not" => {
+                let [atom] = &items[1..] else {
+                    return self.error(sexp.position(), "expected `(not <atom>)`");
+                };
+                let mut literal = self.literal(atom)?;
+                literal.positive = false;
+                out.push(literal);
+            }
+            "increase" => {
+                let Some(cost) = cost else {
+                    return self.error(sexp.position(), "`increase` is only allowed in effects");
+                };
+                match &items[1..] {
+                    [Sexp::List(fluent, _), amount] if matches!(fluent.as_slice(), [Sexp::Atom(name, _)] if name == "total-cost") =>
+                    {
+                        let Ok(amount) = self.atom(amount, "a cost")?.parse::<i32>() else {
+                            return self.error(amount.position(), "expected an integer cost");
+                        };
+                        *cost += amount;
+                    }
+                    _ => {
+                        return self
+                            .error(sexp.position(), "expected `(increase (total-cost) <n>)`")
+                    }
+                }
+            }
+            _ => out.push(self.literal(sexp)?),
+        }
+        Ok(())
+    }
+
+    fn literal(&self, sexp: &Sexp) -> Result<Literal, PddlError> {
+        let items = self.list(sexp, "an atom")?;
+        let Some(head) = items.first() else {
+            return self.error(sexp.position(), "expected an atom");
+        };
+        let predicate = self.atom(head, "a predicate")?;
+        if UNSUPPORTED.contains(&predicate) {
+            return self.error(head.position(), format!("`{predicate}` is not supported"));
+        }
+        let arguments = items[1..]
+            .iter()
+            .map(|item| self.atom(item, "an argument").map(str::to_string))
+            .collect::<Result<_, _>>()?;
+        Ok(Literal {
+            positive: true,
+            predicate: predicate.to_string(),
+            arguments,
+            position: sexp.position(),
+        })
+    }
+
+    /// Checks that a literal uses a known predicate with the right number of arguments,
+    /// each of which is one of the given names.
+    fn check_literal(
+        &self,
+        literal: &Literal,
+        domain: &Domain,
+        names: &HashSet<&str>,
+    ) -> Result<(), PddlError> {
+        let arity = match domain.predicates.get(&literal.predicate) {
+            Some(&arity) => arity,
+            None if literal.predicate == "=" => 2,
+            None => {
+                let message = format!("unknown predicate `{}`", literal.predicate);
+                return self.error(literal.position, message);
+            }
+        };
+        if literal.arguments.len() != arity {
+            let message = format!(
+                "`{}` takes {arity} arguments, but {} were given",
+                literal.predicate,
+                literal.arguments.len()
+            );
+            return self.error(literal.position, message);
+        }
+        for argument in &literal.arguments {
+            if !names.contains(argument.as_str()) {
+                let message = format!("unknown argument `{argument}`");
+                return self.error(literal.position, message);
+            }
+        }
+        Ok(())
+    }
+
+    fn domain(&self, source: &str) -> Result<Domain, PddlError> {
+        let sexp = self.read(source)?;
+        let (name, sections) = self.definition(&sexp, "domain")?;
+        let mut domain = Domain {
+            name: name.to_string(),
+            types: HashMap::new(),
+            constants: vec![],
+            predicates: HashMap::new(),
+            actions: vec![],
+        };
+
+        for section in sections {
+            let (keyword, items) = self.section(section)?;
+            match keyword {
+                ":requirements" => {
+                    for item in items {
+                        let requirement = self.atom(item, "a requirement")?;
+                        if !REQUIREMENTS.contains(&requirement) {
+                            let message = format!("unsupported requirement `{requirement}`");
+                            return self.error(item.position(), message);
+                        }
+                    }
+                }
+                ":types" => {
+                    let types = self.typed_list(items)?;
+                    for (name, parent, _) in &types {
+                        domain.types.insert(name.clone(), parent.clone());
+                    }
+                    for (_, parent, position) in types {
+                        if !domain.has_type(&parent) {
+                            return self.error(position, format!("unknown type `{parent}`"));
+                        }
+                    }
+                }
+                ":constants" => {
+                    for (name, kind, position) in self.typed_list(items)? {
+                        if !domain.has_type(&kind) {
+                            return self.error(position, format!("unknown type `{kind}`"));
+                        }
+                        domain.constants.push((name, kind));
+                    }
+                }
+                ":predicates" => {
+                    for item in items {
+                        let declaration = self.list(item, "a predicate declaration")?;
+                        let Some(head) = declaration.first() else {
+                            return self.error(item.position(), "expected a predicate declaration");
+                        };
+                        let name = self.atom(head, "a predicate")?;
+                        let parameters = self.typed_list(&declaration[1..])?;
+                        for (_, kind, position) in &parameters {
+                            if !domain.has_type(kind) {
+                                return self.error(*position, format!("unknown type `{kind}`"));
+                            }
+                        }
+                        domain.predicates.insert(name.to_string(), parameters.len());
+                    }
+                }
+                ":action" => {
+                    let schema = self.action(section, items, &domain)?;
+                    domain.actions.push(schema);
+                }
+                ":functions" => {}
+                _ => {
+                    let message = format!("unsupported section `{keyword}`");
+                    return self.error(section.position(), message);
+                }
+            }
+        }
+        Ok(domain)
+    }
+
+    fn action(&self, section: &Sexp, items: &[Sexp], domain: &Domain) -> Result<Schema, PddlError> {
+        let Some(name) = items.first() else {
+            return self.error(section.position(), "expected an action name");
+        };
+        let mut schema = Schema {
+            name: self.atom(name, "an action name")?.to_string(),
+            parameters: vec![],
+            preconditions: vec![],
+            effects: vec![],
+            cost: 0,
+        };
+        let mut has_cost = false;
+
+        let mut rest = items[1..].iter();
+        while let Some(keyword) = rest.next() {
+            let key = self.atom(keyword, "`:parameters`, `:precondition` or `:effect`")?;
+            let Some(value) = rest.next() else {
+                return self.error(
+                    keyword.position(),
+                    format!("expected a value after `{key}`"),
+                );
+            };
+            match key {
+                ":parameters" => {
+                    for (name, kind, position) in
+                        self.typed_list(self.list(value, "parameters")?)?
+                    {
+                        if !name.starts_with('?') {
+                            let message = format!("parameter `{name}` must start with `?`");
+                            return self.error(position, message);
+                        }
+                        if !domain.has_type(&kind) {
+                            return self.error(position, format!("unknown type `{kind}`"));
+                        }
+                        schema.parameters.push((name, kind));
+                    }
+                }
+                ":precondition" => self.literals(value, &mut schema.preconditions, None)?,
+                ":effect" => {
+                    self.literals(value, &mut schema.effects, Some(&mut schema.cost))?;
+                    has_cost = self.mentions_cost(value);
+                }
+                _ => {
+                    return self.error(keyword.position(), format!("unknown action field `{key}`"))
+                }
+            }
+        }
+        if !has_cost {
+            schema.cost = 1;
+        }
+
+        let names: HashSet<&str> = schema
+            .parameters
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .chain(domain.constants.iter().map(|(name, _)| name.as_str()))
+            .collect();
+        for literal in schema.preconditions.iter().chain(&schema.effects) {
+            self.check_literal(literal, domain, &names)?;
+        }
+        if let Some(literal) = schema
+            .effects
+            .iter()
+            .find(|literal| literal.predicate == "=")
+        {
+            return self.error(literal.position, "effects can't change equality");
+        }
+        Ok(schema)
+    }
+
+    fn mentions_cost(&self, sexp: &Sexp) -> bool {
+        match sexp {
+            Sexp::Atom(text, _) => text == "total-cost",
+            Sexp::List(items, _) => items.iter().any(|item| self.mentions_cost(item)),
+        }
+    }
+
+    fn problem(&self, source: &str, domain: &Domain) -> Result<Problem, PddlError> {
+        let sexp = self.read(source)?;
+        let (name, sections) = self.definition(&sexp, "problem")?;
+        let mut problem = Problem {
+            name: name.to_string(),
+            objects: domain.constants.clone(),
+            init: vec![],
+            goal: vec![],
+        };
+        let mut init = vec![];
+
+        for section in sections {
+            let (keyword, items) = self.section(section)?;
+            match keyword {
+                ":domain" => {
+                    let [name] = items else {
+                        return self.error(section.position(), "expected `(:domain <name>)`");
+                    };
+                    let name_text = self.atom(name, "a domain name")?;
+                    if name_text != domain.name {
+                        let message = format!(
+                            "problem is for domain `{name_text}`, but the domain is `{}`",
+                            domain.name
+                        );
+                        return self.error(name.position(), message);
+                    }
+                }
+                ":objects" => {
+                    for (name, kind, position) in self.typed_list(items)? {
+                        if !domain.has_type(&kind) {
+                            return self.error(position, format!("unknown type `{kind}`"));
+                        }
+                        problem.objects.push((name, kind));
+                    }
+                }
+                ":init" => {
+                    for item in items {
+                        // Numeric fluents like `(= (total-cost) 0)` only matter for the metric
+                        if let Sexp::List(fact, _) = item {
+                            if matches!(fact.first(), Some(Sexp::Atom(head, _)) if head == "=") {
+                                continue;
+                            }
+                        }
+                        init.push(self.literal(item)?);
+                    }
+                }
+                ":goal" => {
+                    let [goal] = items else {
+                        return self.error(section.position(), "expected `(:goal <condition>)`");
+                    };
+                    self.literals(goal, &mut problem.goal, None)?;
+                }
+                ":metric" => {}
+                _ => {
+                    let message = format!("unsupported section `{keyword}`");
+                    return self.error(section.position(), message);
+                }
+            }
+        }
+
+        let names: HashSet<&str> = problem
+            .objects
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        for literal in init.iter().chain(&problem.goal) {
+            self.check_literal(literal, domain, &names)?;
+        }
+        problem.init = init;
+        Ok(problem)
+    }
+}
+
+/// Assigns a fact key to each ground atom.
+#[derive(Default)]
+struct Atoms {
+    keys: HashMap<(String, Vec<String>), usize>,
+    atoms: Vec<(String, Vec<String>)>,
+}
+
+impl Atoms {
+    fn key(&mut self, predicate: &str, arguments: Vec<String>) -> usize {
+        let atom = (predicate.to_string(), arguments);
+        if let Some(&key) = self.keys.get(&atom) {
+            return key;
+        }
+        let key = self.atoms.len();
+        self.keys.insert(atom.clone(), key);
+        self.atoms.push(atom);
+        key
+    }
+}
+
+fn format_atom(name: &str, arguments: &[String]) -> String {
+    let mut text = format!("({name}");
+    for argument in arguments {
+        text.push(' ');
+        text.push_str(argument);
+    }
+    text.push(')');
+    text
+}
+
+/// Instantiates the action schemas with every combination of objects of the right types.
+fn ground(domain: &Domain, problem: &Problem) -> PddlProblem {
+    let fluents: HashSet<&str> = domain
+        .actions
+        .iter()
+        .flat_map(|schema| &schema.effects)
+        .map(|literal| literal.predicate.as_str())
+        .collect();
+    let init: HashSet<(&str, &[String])> = problem
+        .init
+        .iter()
+        .map(|literal| (literal.predicate.as_str(), literal.arguments.as_slice()))
+        .collect();
+    let holds = |predicate: &str, arguments: &[String]| match predicate {
+        "=" => arguments[0] == arguments[1],
+        _ => init.contains(&(predicate, arguments)),
+    };
+
+    let mut atoms = Atoms::default();
+    let mut actions = vec![];
+    for schema in &domain.actions {
+        let candidates: Vec<Vec<&str>> = schema
+            .parameters
+            .iter()
+            .map(|(_, kind)| {
+                problem
+                    .objects
+                    .iter()
+                    .filter(|(_, object_kind)| domain.is_subtype(object_kind, kind))
+                    .map(|(name, _)| name.as_str())
+                    .collect()
+            })
+            .collect();
+        if candidates.iter().any(Vec::is_empty) {
+            continue;
+        }
+
+        let mut indices = vec![0; candidates.len()];
+        loop {
+            let binding: HashMap<&str, &str> = schema
+                .parameters
+                .iter()
+                .zip(&indices)
+                .zip(&candidates)
+                .map(|(((name, _), &index), objects)| (name.as_str(), objects[index]))
+                .collect();
+            let bind = |literal: &Literal| -> Vec<String> {
+                literal
+                    .arguments
+                    .iter()
+                    .map(|argument| {
+                        binding
+                            .get(argument.as_str())
+                            .copied()
+                            .unwrap_or(argument)
+                            .to_string()
+                    })
+                    .collect()
+            };
+
+            let mut action = StripsAction::new(format_atom(
+                &schema.name,
+                &indices
+                    .iter()
+                    .zip(&candidates)
+                    .map(|(&index, objects)| objects[index].to_string())
+                    .collect::<Vec<_>>(),
+            ))
+            .with_cost(schema.cost);
+            let mut applicable = true;
+            for literal in &schema.preconditions {
+                let arguments = bind(literal);
+                if fluents.contains(literal.predicate.as_str()) {
+                    action =
+                        action.requires(atoms.key(&literal.predicate, arguments), literal.positive);
+                } else if holds(&literal.predicate, &arguments) != literal.positive {
+                    applicable = false;
+                    break;
+                }
+            }
+            if applicable {
+                // Deletes are applied before adds, so an atom which is both deleted and added ends up true
+                for literal in schema.effects.iter().filter(|literal| !literal.positive) {
+                    action = action.deletes(atoms.key(&literal.predicate, bind(literal)));
+                }
+                for literal in schema.effects.iter().filter(|literal| literal.positive) {
+                    action = action.adds(atoms.key(&literal.predicate, bind(literal)));
+                }
+                actions.push(action);
+            }
+
+            // Move on to the next combination of objects
+            let mut position = 0;
+            while position < indices.len() {
+                indices[position] += 1;
+                if indices[position] < candidates[position].len() {
+                    break;
+                }
+                indices[position] = 0;
+                position += 1;
+            }
+            if position == indices.len() {
+                break;
+            }
+        }
+    }
+
+    let mut goal = WorldState::new();
+    for literal in &problem.goal {
+        let key = atoms.key(&literal.predicate, literal.arguments.clone());
+        goal.set(key, literal.positive);
+    }
+
+    let mut initial_state = WorldState::new();
+    for (key, (predicate, arguments)) in atoms.atoms.iter().enumerate() {
+        initial_state.set(key, holds(predicate, arguments));
+    }
+
+    PddlProblem {
+        domain_name: domain.name.clone(),
+        problem_name: problem.name.clone(),
+        facts: atoms
+            .atoms
+            .iter()
+            .map(|(predicate, arguments)| format_atom(predicate, arguments))
+            .collect(),
+        initial_state,
+        goal,
+        actions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{plan, try_plan, SearchBudget};
+
+    const DOMAIN: &str = "
+; Robots carrying parcels between rooms
+(define (domain Delivery)
+  (:requirements :strips :typing :negative-preconditions :equality :action-costs)
+  (:types room item - object parcel - item)
+  (:constants depot - room)
+  (:predicates (at-robot ?r - room) (at ?i - item ?r - room)
+               (holding ?i - item) (connected ?a ?b - room) (busy))
+  (:functions (total-cost))
+  (:action move
+    :parameters (?from ?to - room)
+    :precondition (and (at-robot ?from) (connected ?from ?to) (not (= ?from ?to)))
+    :effect (and (not (at-robot ?from)) (at-robot ?to) (increase (total-cost) 2)))
+  (:action pick
+    :parameters (?i - item ?r - room)
+    :precondition (and (at-robot ?r) (at ?i ?r) (not (busy)))
+    :effect (and (holding ?i) (busy) (not (at ?i ?r))))
+  (:action drop
+    :parameters (?i - item ?r - room)
+    :precondition (and (at-robot ?r) (holding ?i))
+    :effect (and (at ?i ?r) (not (holding ?i)) (not (busy)))))
+";
+
+    const PROBLEM: &str = "
+(define (problem two-parcels)
+  (:domain delivery)
+  (:objects kitchen hall - room a b - parcel)
+  (:init (at-robot hall) (at a kitchen) (at b hall)
+         (connected hall kitchen) (connected kitchen hall)
+         (connected hall depot) (connected depot hall)
+         (= (total-cost) 0))
+  (:goal (and (at a depot) (at b depot)))
+  (:metric minimize (total-cost)))
+";
+
+    #[test]
+    fn pddl_grounding() {
+        let problem = PddlProblem::parse(DOMAIN, PROBLEM).unwrap();
+        assert_eq!(problem.domain_name(), "delivery");
+        assert_eq!(problem.problem_name(), "two-parcels");
+
+        // Moves are only grounded between connected rooms
+        let moves: Vec<_> = problem
+            .actions()
+            .iter()
+            .map(|action| action.name())
+            .filter(|name| name.starts_with("(move"))
+            .collect();
+        assert_eq!(moves.len(), 4);
+        assert!(moves.contains(&"(move hall depot)"));
+        assert_eq!(problem.actions().len(), 4 + 6 + 6);
+
+        let key = problem.fact_key("(at a kitchen)").unwrap();
+        assert_eq!(problem.fact_name(key), Some("(at a kitchen)"));
+        assert_eq!(problem.initial_state().get_bool(key), Some(true));
+        let key = problem.fact_key("(holding b)").unwrap();
+        assert_eq!(problem.initial_state().get_bool(key), Some(false));
+        assert_eq!(problem.fact_key("(connected hall kitchen)"), None);
+    }
+
+    #[test]
+    fn pddl_planning() {
+        let problem = PddlProblem::parse(DOMAIN, PROBLEM).unwrap();
+        let (path, cost) =
+            plan(problem.initial_state(), problem.actions(), problem.goal()).unwrap();
+        // Each parcel is carried separately, with five moves and four picks or drops
+        assert_eq!(path.len(), 9);
+        assert_eq!(cost, 14);
+
+        let goal = problem.fact_goal(RelaxedHeuristic::HMax);
+        let budget = SearchBudget::unlimited();
+        let plan = try_plan(problem.initial_state(), problem.actions(), &goal, &budget).unwrap();
+        assert_eq!(plan.cost(), cost);
+    }
+
+    #[test]
+    fn pddl_errors() {
+        let error = |domain: &str, problem: &str| {
+            let error = PddlProblem::parse(domain, problem).unwrap_err();
+            (error.file, error.line, error.column, error.message)
+        };
+
+        let domain = DOMAIN.replace("(holding ?i) (busy)", "(carrying ?i) (busy)");
+        assert_eq!(
+            error(&domain, PROBLEM),
+            (
+                PddlFile::Domain,
+                17,
+                18,
+                "unknown predicate `carrying`".to_string()
+            )
+        );
+
+        let domain = DOMAIN.replace(":action-costs", ":conditional-effects");
+        let (file, line, column, message) = error(&domain, PROBLEM);
+        assert_eq!((file, line, column), (PddlFile::Domain, 4, 68));
+        assert_eq!(message, "unsupported requirement `:conditional-effects`");
+
+        let problem = PROBLEM.replace("(:domain delivery)", "(:domain logistics)");
+        assert_eq!(
+            error(DOMAIN, &problem),
+            (
+                PddlFile::Problem,
+                3,
+                12,
+                "problem is for domain `logistics`, but the domain is `delivery`".to_string()
+            )
+        );
+
+        let problem = PROBLEM.replace("(at b hall)", "(at b garden)");
+        let (file, line, _, message) = error(DOMAIN, &problem);
+        assert_eq!((file, line), (PddlFile::Problem, 5));
+        assert_eq!(message, "unknown argument `garden`");
+
+        let problem = PROBLEM.replace(
+            "(:metric minimize (total-cost)))",
+            "(:metric minimize (total-cost))",
+        );
+        assert_eq!(
+            error(DOMAIN, &problem),
+            (PddlFile::Problem, 2, 1, "unclosed `(`".to_string())
+        );
+
+        let error =
+            PddlProblem::parse(DOMAIN, "(define (problem p) (:goal (at-robot)))").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "problem:1:28: `at-robot` takes 1 arguments, but 0 were given"
+        );
+    }
+}
@@ -1,48 +1,553 @@
-use crate::{Action, Goal};
-use pathfinding::prelude::astar;
-use std::hash::{Hash, Hasher};
+use crate::{
+    Action, BudgetExhausted, Cost, Goal, PlanError, SearchBudget, SearchStats, SuccessorGenerator,
+};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::slice;
 
 #[derive(PartialEq, Eq, Clone)]
-struct PlanNode<'a, S, A>
+struct PlanNode<S, A, C>
 where
     S: Clone + Hash + Eq,
-    A: Action<S> + Eq + Clone + Hash,
+    A: Action<S, C> + Eq + Clone + Hash,
+    C: Cost,
 {
     pub state: S,
-    pub action: Option<&'a A>,
+    pub action: Option<A>,
+    _cost: PhantomData<C>,
 }
 
-impl<'a, S, A> PlanNode<'a, S, A>
+impl<S, A, C> PlanNode<S, A, C>
 where
     S: Clone + Hash + Eq,
-    A: Action<S> + Eq + Clone + Hash,
+    A: Action<S, C> + Eq + Clone + Hash,
+    C: Cost,
 {
+    /// Returns the node at the start of a plan.
+    fn root(state: S) -> PlanNode<S, A, C> {
+        PlanNode {
+            state,
+            action: None,
+            _cost: PhantomData,
+        }
+    }
+
     /// Returns the next node after applying the given action.
-    fn child(&self, action: &'a A) -> PlanNode<'a, S, A> {
+    fn child(&self, action: A) -> PlanNode<S, A, C> {
         PlanNode {
             state: action.apply(&self.state),
             action: Some(action),
+            _cost: PhantomData,
         }
     }
 
-    /// Returns all possible next nodes using the given actions.
-    pub fn children(&self, actions: &'a Vec<A>) -> Vec<(PlanNode<'a, S, A>, i32)> {
+    /// Returns all possible next nodes using the actions produced for this state.
+    pub fn children<P>(&self, successors: &P) -> Vec<(PlanNode<S, A, C>, C)>
+    where
+        P: SuccessorGenerator<S, A, C> + ?Sized,
+    {
+        let mut actions = Vec::new();
+        successors.applicable(&self.state, &mut actions);
         actions
-            .iter()
-            .filter(|action| action.is_applicable(&self.state))
-            .map(|action| (self.child(action), action.cost(&self.state)))
+            .into_iter()
+            .map(|action| {
+                let cost = action.cost(&self.state);
+                (self.child(action), cost)
+            })
             .collect()
     }
 }
 
-impl<'a, S, A> Hash for PlanNode<'a, S, A>
+/// A node in the search tree, linked to the node it was reached from.
+struct SearchNode<S, A, C>
 where
     S: Clone + Hash + Eq,
-    A: Action<S> + Eq + Clone + Hash,
+    A: Action<S, C> + Eq + Clone + Hash,
+    C: Cost,
 {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.action.map(|action| action.hash(state));
-        self.state.hash(state);
+    node: PlanNode<S, A, C>,
+    parent: Option<usize>,
+    cost: C,
+    depth: usize,
+    closed: bool,
+}
+
+/// An entry in the open list, ordered so that the lowest estimated total cost is popped first.
+#[derive(PartialEq, Eq)]
+struct OpenEntry<C> {
+    estimate: C,
+    cost: C,
+    index: usize,
+}
+
+impl<C: Ord> Ord for OpenEntry<C> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Prefer lower estimates, then nodes further from the start, then older nodes
+        other
+            .estimate
+            .cmp(&self.estimate)
+            .then_with(|| self.cost.cmp(&other.cost))
+            .then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+impl<C: Ord> PartialOrd for OpenEntry<C> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The result of a finished search.
+pub(crate) enum SearchOutcome {
+    /// A goal node was found at the given index.
+    Found(usize),
+    /// Every reachable state was searched without finding the goal.
+    Exhausted,
+    /// The budget ran out, or a limit pruned part of the state space.
+    BudgetExhausted,
+}
+
+/// The order in which a `Search` expands nodes, for the strategies which keep a single open list.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum NodeOrder {
+    AStar,
+    BreadthFirst,
+    UniformCost,
+    Greedy,
+    /// A* with the heuristic multiplied by a weight of at least 1.
+    WeightedAStar(f64),
+}
+
+/// A best-first search over plan nodes which can be limited by a `SearchBudget`.
+///
+/// Nodes are ordered as in A* by default.
+pub(crate) struct Search<S, A, C>
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    C: Cost,
+{
+    nodes: Vec<SearchNode<S, A, C>>,
+    index: HashMap<S, usize>,
+    open: BinaryHeap<OpenEntry<C>>,
+    expanded: usize,
+    deepest: usize,
+    pruned: bool,
+    order: NodeOrder,
+    /// Plans must cost less than this, if set.
+    cost_below: Option<C>,
+}
+
+impl<S, A, C> Search<S, A, C>
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    C: Cost,
+{
+    /// Creates an A* search starting from the given state.
+    pub fn new<G>(initial_state: &S, goal: &G) -> Self
+    where
+        G: Goal<S, C>,
+    {
+        Self::with_order(initial_state, goal, NodeOrder::AStar)
+    }
+
+    /// Creates a search starting from the given state, which expands nodes in the given order.
+    pub fn with_order<G>(initial_state: &S, goal: &G, order: NodeOrder) -> Self
+    where
+        G: Goal<S, C>,
+    {
+        let mut search = Self {
+            nodes: Vec::new(),
+            index: HashMap::new(),
+            open: BinaryHeap::new(),
+            expanded: 0,
+            deepest: 0,
+            pruned: false,
+            order,
+            cost_below: None,
+        };
+        let initial = PlanNode::root(initial_state.clone());
+        search.insert(initial, None, C::ZERO, 0, goal);
+        search
+    }
+
+    /// Only searches for plans which cost strictly less than the given cost.
+    ///
+    /// Unlike a budget's `max_cost`, pruning these plans doesn't mean the budget ran out.
+    pub fn with_cost_below(mut self, cost: C) -> Self {
+        self.cost_below = Some(cost);
+        self
+    }
+
+    /// Returns the number of states expanded so far.
+    pub fn expanded(&self) -> usize {
+        self.expanded
+    }
+
+    /// Adds a node to the open list, unless its state was already reached more cheaply.
+    fn insert<G>(
+        &mut self,
+        node: PlanNode<S, A, C>,
+        parent: Option<usize>,
+        cost: C,
+        depth: usize,
+        goal: &G,
+    ) where
+        G: Goal<S, C>,
+    {
+        let index = match self.index.get(&node.state) {
+            Some(&index) => {
+                let existing = &mut self.nodes[index];
+                // Breadth-first and greedy searches never reopen states
+                let reopen = !matches!(self.order, NodeOrder::BreadthFirst | NodeOrder::Greedy);
+                if !reopen || existing.cost <= cost {
+                    return;
+                }
+                *existing = SearchNode {
+                    node,
+                    parent,
+                    cost,
+                    depth,
+                    closed: false,
+                };
+                index
+            }
+            None => {
+                let index = self.nodes.len();
+                self.index.insert(node.state.clone(), index);
+                self.nodes.push(SearchNode {
+                    node,
+                    parent,
+                    cost,
+                    depth,
+                    closed: false,
+                });
+                index
+            }
+        };
+        if depth > self.nodes[self.deepest].depth {
+            self.deepest = index;
+        }
+        let state = &self.nodes[index].node.state;
+        let entry = match self.order {
+            // Ignoring costs leaves the oldest nodes, which are the shallowest, first
+            NodeOrder::BreadthFirst => OpenEntry {
+                estimate: C::ZERO,
+                cost: C::ZERO,
+                index,
+            },
+            NodeOrder::UniformCost => OpenEntry {
+                estimate: cost,
+                cost,
+                index,
+            },
+            NodeOrder::Greedy => OpenEntry {
+                estimate: goal.heuristic(state),
+                cost,
+                index,
+            },
+            NodeOrder::WeightedAStar(weight) => OpenEntry {
+                estimate: cost + goal.heuristic(state).scale(weight),
+                cost,
+                index,
+            },
+            NodeOrder::AStar => OpenEntry {
+                estimate: cost + goal.heuristic(state),
+                cost,
+                index,
+            },
+        };
+        self.open.push(entry);
+    }
+
+    /// Expands the most promising open node.
+    ///
+    /// Returns `None` while the search should continue.
+    pub fn step<G, P>(
+        &mut self,
+        actions: &P,
+        goal: &G,
+        budget: &SearchBudget<C>,
+    ) -> Option<SearchOutcome>
+    where
+        G: Goal<S, C>,
+        P: SuccessorGenerator<S, A, C> + ?Sized,
+    {
+        let Some(entry) = self.open.pop() else {
+            return Some(if self.pruned {
+                SearchOutcome::BudgetExhausted
+            } else {
+                SearchOutcome::Exhausted
+            });
+        };
+        let current = &self.nodes[entry.index];
+        if current.closed || current.cost < entry.cost {
+            return None; // Stale entry, the state was reached more cheaply since
+        }
+        if goal.is_satisfied(&current.node.state) {
+            return Some(SearchOutcome::Found(entry.index));
+        }
+        if budget.is_spent(self.expanded) {
+            self.open.push(entry);
+            return Some(SearchOutcome::BudgetExhausted);
+        }
+
+        let (cost, depth) = (current.cost, current.depth + 1);
+        let children = current.node.children(actions);
+        self.nodes[entry.index].closed = true;
+        self.expanded += 1;
+        for (child, step_cost) in children {
+            let child_cost = cost + step_cost;
+            if self.cost_below.is_some_and(|below| child_cost >= below) {
+                continue;
+            }
+            if budget.allows(depth, child_cost) {
+                self.insert(child, Some(entry.index), child_cost, depth, goal);
+            } else {
+                self.pruned = true;
+            }
+        }
+        None
+    }
+
+    /// Runs the search until it finishes.
+    pub fn run<G, P>(&mut self, actions: &P, goal: &G, budget: &SearchBudget<C>) -> SearchOutcome
+    where
+        G: Goal<S, C>,
+        P: SuccessorGenerator<S, A, C> + ?Sized,
+    {
+        loop {
+            if let Some(outcome) = self.step(actions, goal, budget) {
+                return outcome;
+            }
+        }
+    }
+
+    /// Returns diagnostics about the search so far.
+    pub fn stats(&self) -> SearchStats<S> {
+        let deepest = &self.nodes[self.deepest];
+        SearchStats {
+            expanded: self.expanded,
+            frontier: self.nodes.iter().filter(|node| !node.closed).count(),
+            deepest_state: deepest.node.state.clone(),
+            max_depth: deepest.depth,
+        }
+    }
+
+    /// Converts the outcome of this search into a plan or an error.
+    pub fn result(&self, outcome: SearchOutcome) -> Result<Plan<S, A, C>, PlanError<S>> {
+        match outcome {
+            SearchOutcome::Found(index) => Ok(self.path(index)),
+            SearchOutcome::Exhausted => Err(PlanError::Exhausted(self.stats())),
+            SearchOutcome::BudgetExhausted => Err(PlanError::BudgetExhausted(self.stats())),
+        }
+    }
+
+    /// Returns the plan leading to the node at the given index.
+    pub fn path(&self, index: usize) -> Plan<S, A, C> {
+        let mut path = Vec::new();
+        let mut current = index;
+        while let Some(parent) = self.nodes[current].parent {
+            path.push(current);
+            current = parent;
+        }
+
+        let mut steps = Vec::with_capacity(path.len());
+        let mut previous = &self.nodes[current];
+        for &index in path.iter().rev() {
+            let node = &self.nodes[index];
+            if let Some(action) = &node.node.action {
+                steps.push(PlanStep {
+                    action: action.clone(),
+                    pre_state: previous.node.state.clone(),
+                    post_state: node.node.state.clone(),
+                    cost: action.cost(&previous.node.state),
+                    cumulative_cost: node.cost,
+                });
+            }
+            previous = node;
+        }
+        Plan { steps }
+    }
+}
+
+/// A single step of a plan, with the states before and after its action.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PlanStep<S, A, C = i32> {
+    /// The action taken in this step.
+    pub action: A,
+    /// The state the action is applied to.
+    pub pre_state: S,
+    /// The state expected after applying the action.
+    pub post_state: S,
+    /// The cost of the action in `pre_state`.
+    pub cost: C,
+    /// The total cost of the plan up to and including this step.
+    pub cumulative_cost: C,
+}
+
+/// A sequence of actions along with the states and costs they pass through.
+///
+/// Plans are returned by the `try_` planning functions and `PlannerTask`.
+/// Each step records the state its action is applied to and the state it is expected to produce,
+/// so executors can check the world is following the plan, and debugging tools can show its trajectory.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+///
+/// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+/// struct Counter(u32);
+///
+/// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+/// enum Add {
+///     One,
+///     Two,
+/// }
+///
+/// impl Action<Counter> for Add {
+///     fn is_applicable(&self, _state: &Counter) -> bool {
+///         true
+///     }
+///
+///     fn apply_mut(&self, state: &mut Counter) {
+///         state.0 += match self {
+///             Add::One => 1,
+///             Add::Two => 2,
+///         };
+///     }
+///
+///     fn cost(&self, _state: &Counter) -> i32 {
+///         match self {
+///             Add::One => 1,
+///             Add::Two => 3,
+///         }
+///     }
+/// }
+///
+/// let plan = Plan::from_actions(&Counter(0), vec![Add::Two, Add::One]);
+///
+/// assert_eq!(plan.len(), 2);
+/// assert_eq!(plan.cost(), 4);
+/// assert_eq!(plan.final_state(), Some(&Counter(3)));
+///
+/// let states: Vec<_> = plan.states().collect();
+/// assert_eq!(states, vec![&Counter(0), &Counter(2), &Counter(3)]);
+///
+/// let costs: Vec<_> = plan.iter().map(|step| step.cumulative_cost).collect();
+/// assert_eq!(costs, vec![3, 4]);
+///
+/// assert_eq!(plan.into_actions(), vec![Add::Two, Add::One]);
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Plan<S, A, C = i32> {
+    steps: Vec<PlanStep<S, A, C>>,
+}
+
+impl<S, A, C> Plan<S, A, C>
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    C: Cost,
+{
+    /// Creates a plan by applying the given actions in order, starting from the given state.
+    ///
+    /// The actions are not checked for applicability.
+    pub fn from_actions(initial_state: &S, actions: Vec<A>) -> Self {
+        let mut steps: Vec<PlanStep<S, A, C>> = Vec::with_capacity(actions.len());
+        for action in actions {
+            let (pre_state, total) = match steps.last() {
+                Some(step) => (step.post_state.clone(), step.cumulative_cost),
+                None => (initial_state.clone(), C::ZERO),
+            };
+            let cost = action.cost(&pre_state);
+            steps.push(PlanStep {
+                post_state: action.apply(&pre_state),
+                pre_state,
+                cost,
+                cumulative_cost: total + cost,
+                action,
+            });
+        }
+        Plan { steps }
+    }
+}
+
+impl<S, A, C: Cost> Plan<S, A, C> {
+    /// Returns the steps of the plan in order.
+    pub fn steps(&self) -> &[PlanStep<S, A, C>] {
+        &self.steps
+    }
+
+    /// Returns an iterator over the steps of the plan.
+    pub fn iter(&self) -> slice::Iter<'_, PlanStep<S, A, C>> {
+        self.steps.iter()
+    }
+
+    /// Returns an iterator over the actions of the plan.
+    pub fn actions(&self) -> impl Iterator<Item = &A> {
+        self.steps.iter().map(|step| &step.action)
+    }
+
+    /// Returns an iterator over the states the plan passes through, starting with the initial state.
+    ///
+    /// An empty plan yields no states.
+    pub fn states(&self) -> impl Iterator<Item = &S> {
+        self.steps
+            .first()
+            .map(|step| &step.pre_state)
+            .into_iter()
+            .chain(self.steps.iter().map(|step| &step.post_state))
+    }
+
+    /// Returns the state expected once the plan has been completed, unless the plan is empty.
+    pub fn final_state(&self) -> Option<&S> {
+        self.steps.last().map(|step| &step.post_state)
+    }
+
+    /// Returns the total cost of the plan.
+    pub fn cost(&self) -> C {
+        self.steps
+            .last()
+            .map_or(C::ZERO, |step| step.cumulative_cost)
+    }
+
+    /// Returns the number of steps in the plan.
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Returns true if the plan has no steps.
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Consumes the plan and returns its actions.
+    pub fn into_actions(self) -> Vec<A> {
+        self.steps.into_iter().map(|step| step.action).collect()
+    }
+}
+
+impl<S, A, C> IntoIterator for Plan<S, A, C> {
+    type Item = PlanStep<S, A, C>;
+    type IntoIter = std::vec::IntoIter<PlanStep<S, A, C>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.steps.into_iter()
+    }
+}
+
+impl<'a, S, A, C> IntoIterator for &'a Plan<S, A, C> {
+    type Item = &'a PlanStep<S, A, C>;
+    type IntoIter = slice::Iter<'a, PlanStep<S, A, C>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.steps.iter()
     }
 }
 
@@ -98,31 +603,232 @@ where
 /// assert_eq!(path, vec![]);
 /// assert_eq!(cost, 0);
 /// ```
-pub fn plan<S, A, G>(initial_state: &S, actions: &Vec<A>, goal: &G) -> Option<(Vec<A>, i32)>
+pub fn plan<S, A, G, C>(initial_state: &S, actions: &[A], goal: &G) -> Option<(Vec<A>, C)>
 where
     S: Clone + Hash + Eq,
-    A: Action<S> + Eq + Clone + Hash,
-    G: Goal<S> + Clone,
+    A: Action<S, C> + Eq + Clone + Hash,
+    G: Goal<S, C> + Clone,
+    C: Cost,
 {
-    let initial = PlanNode {
-        state: initial_state.clone(),
-        action: None,
+    plan_with_budget(initial_state, actions, goal, &SearchBudget::unlimited())
+        .ok()
+        .flatten()
+}
+
+/// Returns a sequence of actions to reach the goal while minimizing cost, searching within the given budget.
+///
+/// Returns `Ok(None)` if no plan exists, and `Err(BudgetExhausted)` if the budget ran out first.
+/// Plans which are longer or more expensive than the budget allows are never returned.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+///
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// struct Counter(u32);
+///
+/// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+/// struct Increment;
+///
+/// impl Action<Counter> for Increment {
+///     fn is_applicable(&self, _state: &Counter) -> bool {
+///         true
+///     }
+///
+///     fn apply_mut(&self, state: &mut Counter) {
+///         state.0 += 1;
+///     }
+/// }
+///
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// struct Reach(u32);
+///
+/// impl Goal<Counter> for Reach {
+///     fn is_satisfied(&self, state: &Counter) -> bool {
+///         state.0 == self.0
+///     }
+/// }
+///
+/// let actions = vec![Increment];
+///
+/// let budget = SearchBudget::unlimited().with_max_depth(5);
+/// let (path, cost) = plan_with_budget(&Counter(0), &actions, &Reach(3), &budget)
+///     .unwrap()
+///     .unwrap();
+/// assert_eq!(path, vec![Increment; 3]);
+/// assert_eq!(cost, 3);
+///
+/// // The goal is too far away, but the state space is infinite so `plan` would never return
+/// let result = plan_with_budget(&Counter(0), &actions, &Reach(10), &budget);
+/// assert_eq!(result, Err(BudgetExhausted));
+/// ```
+pub fn plan_with_budget<S, A, G, C>(
+    initial_state: &S,
+    actions: &[A],
+    goal: &G,
+    budget: &SearchBudget<C>,
+) -> Result<Option<(Vec<A>, C)>, BudgetExhausted>
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    G: Goal<S, C> + Clone,
+    C: Cost,
+{
+    plan_counted(initial_state, actions, goal, budget).0
+}
+
+/// The actions of the best plan and their total cost, `None` if no plan exists, or whether the budget ran out.
+pub(crate) type ActionsResult<A, C> = Result<Option<(Vec<A>, C)>, BudgetExhausted>;
+
+/// Plans within the given budget, also returning the number of states expanded.
+pub(crate) fn plan_counted<S, A, G, C>(
+    initial_state: &S,
+    actions: &[A],
+    goal: &G,
+    budget: &SearchBudget<C>,
+) -> (ActionsResult<A, C>, usize)
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    G: Goal<S, C>,
+    C: Cost,
+{
+    let (result, expanded) = try_plan_counted(initial_state, actions, goal, budget);
+    let result = match result {
+        Ok(plan) => {
+            let cost = plan.cost();
+            Ok(Some((plan.into_actions(), cost)))
+        }
+        Err(PlanError::AlreadySatisfied) => Ok(Some((vec![], C::ZERO))),
+        Err(PlanError::BudgetExhausted(_)) => Err(BudgetExhausted),
+        Err(_) => Ok(None),
     };
-    astar(
-        &initial,
-        |node| node.children(&actions),
-        |node| goal.heuristic(&node.state),
-        |node| goal.is_satisfied(&node.state),
-    )
-    .map(|(path, cost)| {
-        (
-            path.iter()
-                .filter_map(|node| node.action)
-                .cloned()
-                .collect(),
-            cost,
-        )
-    })
+    (result, expanded)
+}
+
+/// Returns a plan to reach the goal while minimizing cost, or the reason none was found.
+///
+/// Unlike `plan_with_budget`, an already satisfied goal is reported as `PlanError::AlreadySatisfied`
+/// rather than an empty plan.
+/// Failed searches report diagnostics which can help to tune the budget or find modelling errors.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+///
+/// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+/// struct Counter(u32);
+///
+/// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+/// struct Increment;
+///
+/// impl Action<Counter> for Increment {
+///     fn is_applicable(&self, state: &Counter) -> bool {
+///         state.0 < 3
+///     }
+///
+///     fn apply_mut(&self, state: &mut Counter) {
+///         state.0 += 1;
+///     }
+/// }
+///
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// struct Reach(u32);
+///
+/// impl Goal<Counter> for Reach {
+///     fn is_satisfied(&self, state: &Counter) -> bool {
+///         state.0 == self.0
+///     }
+/// }
+///
+/// let actions = vec![Increment];
+/// let budget = SearchBudget::unlimited();
+///
+/// let plan = try_plan(&Counter(0), &actions, &Reach(2), &budget).unwrap();
+/// assert_eq!(plan.cost(), 2);
+/// assert_eq!(plan.final_state(), Some(&Counter(2)));
+/// assert_eq!(plan.into_actions(), vec![Increment; 2]);
+///
+/// let result = try_plan(&Counter(0), &actions, &Reach(0), &budget);
+/// assert_eq!(result, Err(PlanError::AlreadySatisfied));
+///
+/// let result = try_plan(&Counter(3), &actions, &Reach(0), &budget);
+/// assert_eq!(result, Err(PlanError::NoApplicableActions));
+///
+/// let Err(PlanError::Exhausted(stats)) = try_plan(&Counter(0), &actions, &Reach(5), &budget) else {
+///     panic!("expected the state space to be exhausted");
+/// };
+/// assert_eq!(stats.expanded, 4);
+/// assert_eq!(stats.deepest_state, Counter(3));
+///
+/// let budget = budget.with_max_expanded(1);
+/// let Err(PlanError::BudgetExhausted(stats)) = try_plan(&Counter(0), &actions, &Reach(2), &budget) else {
+///     panic!("expected the budget to run out");
+/// };
+/// assert_eq!(stats.frontier, 1);
+/// ```
+pub fn try_plan<S, A, G, C>(
+    initial_state: &S,
+    actions: &[A],
+    goal: &G,
+    budget: &SearchBudget<C>,
+) -> Result<Plan<S, A, C>, PlanError<S>>
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    G: Goal<S, C> + Clone,
+    C: Cost,
+{
+    try_plan_counted(initial_state, actions, goal, budget).0
+}
+
+/// The best plan, or the reason none was found.
+pub(crate) type PlanResult<S, A, C> = Result<Plan<S, A, C>, PlanError<S>>;
+
+/// Plans within the given budget, reporting errors and the number of states expanded.
+pub(crate) fn try_plan_counted<S, A, G, C>(
+    initial_state: &S,
+    actions: &[A],
+    goal: &G,
+    budget: &SearchBudget<C>,
+) -> (PlanResult<S, A, C>, usize)
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    G: Goal<S, C>,
+    C: Cost,
+{
+    if let Some(error) = check_initial(initial_state, actions, goal) {
+        return (Err(error), 0);
+    }
+
+    let mut search = Search::new(initial_state, goal);
+    let outcome = search.run(actions, goal, budget);
+    (search.result(outcome), search.expanded())
+}
+
+/// Returns an error if there is no need or no way to search from the initial state.
+pub(crate) fn check_initial<S, A, G, C>(
+    initial_state: &S,
+    actions: &[A],
+    goal: &G,
+) -> Option<PlanError<S>>
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    G: Goal<S, C>,
+    C: Cost,
+{
+    if goal.is_satisfied(initial_state) {
+        Some(PlanError::AlreadySatisfied)
+    } else if !actions
+        .iter()
+        .any(|action| action.is_applicable(initial_state))
+    {
+        Some(PlanError::NoApplicableActions)
+    } else {
+        None
+    }
 }
 
 #[cfg(test)]
@@ -221,4 +927,184 @@ mod tests {
         assert_eq!(path, vec![]);
         assert_eq!(cost, 0);
     }
+
+    #[test]
+    fn plan_budget() {
+        #[derive(PartialEq, Eq, Hash, Clone)]
+        struct Counter(u32);
+
+        #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+        struct Increment;
+
+        impl Action<Counter> for Increment {
+            fn is_applicable(&self, state: &Counter) -> bool {
+                state.0 < 10
+            }
+
+            fn apply_mut(&self, state: &mut Counter) {
+                state.0 += 1;
+            }
+        }
+
+        #[derive(PartialEq, Eq, Hash, Clone)]
+        struct Reach(u32);
+
+        impl Goal<Counter> for Reach {
+            fn is_satisfied(&self, state: &Counter) -> bool {
+                state.0 == self.0
+            }
+        }
+
+        let actions = vec![Increment];
+        let budget = SearchBudget::unlimited();
+
+        // Unreachable goals exhaust the finite state space
+        let result = plan_with_budget(&Counter(0), &actions, &Reach(11), &budget);
+        assert_eq!(result, Ok(None));
+
+        // Pruning by depth or cost means a plan may exist beyond the limit
+        let result = plan_with_budget(&Counter(0), &actions, &Reach(11), &budget.with_max_depth(5));
+        assert_eq!(result, Err(BudgetExhausted));
+        let result = plan_with_budget(&Counter(0), &actions, &Reach(5), &budget.with_max_cost(4));
+        assert_eq!(result, Err(BudgetExhausted));
+
+        let result = plan_with_budget(
+            &Counter(0),
+            &actions,
+            &Reach(5),
+            &budget.with_max_expanded(4),
+        );
+        assert_eq!(result, Err(BudgetExhausted));
+        let (path, cost) = plan_with_budget(
+            &Counter(0),
+            &actions,
+            &Reach(5),
+            &budget.with_max_expanded(5),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(path, vec![Increment; 5]);
+        assert_eq!(cost, 5);
+
+        // A satisfied goal needs no expansions
+        let result = plan_with_budget(
+            &Counter(0),
+            &actions,
+            &Reach(0),
+            &budget.with_max_expanded(0),
+        );
+        assert_eq!(result, Ok(Some((vec![], 0))));
+    }
+
+    #[test]
+    fn plan_steps() {
+        #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+        struct Position(i32);
+
+        #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+        enum Move {
+            Step,
+            Jump,
+        }
+
+        impl Action<Position> for Move {
+            fn is_applicable(&self, _state: &Position) -> bool {
+                true
+            }
+
+            fn apply_mut(&self, state: &mut Position) {
+                state.0 += match self {
+                    Move::Step => 1,
+                    Move::Jump => 3,
+                };
+            }
+
+            fn cost(&self, _state: &Position) -> i32 {
+                match self {
+                    Move::Step => 1,
+                    Move::Jump => 2,
+                }
+            }
+        }
+
+        #[derive(PartialEq, Eq, Hash, Clone)]
+        struct Reach(i32);
+
+        impl Goal<Position> for Reach {
+            fn is_satisfied(&self, state: &Position) -> bool {
+                state.0 == self.0
+            }
+
+            fn heuristic(&self, state: &Position) -> i32 {
+                (self.0 - state.0).max(0) * 2 / 3
+            }
+        }
+
+        let plan = try_plan(
+            &Position(0),
+            &[Move::Step, Move::Jump],
+            &Reach(7),
+            &SearchBudget::unlimited(),
+        )
+        .unwrap();
+        assert_eq!(plan.cost(), 5);
+        assert_eq!(plan.final_state(), Some(&Position(7)));
+        for (step, next) in plan.iter().zip(plan.iter().skip(1)) {
+            assert_eq!(step.post_state, next.pre_state);
+            assert_eq!(step.cumulative_cost + next.cost, next.cumulative_cost);
+        }
+
+        let actions = plan.actions().cloned().collect();
+        assert_eq!(Plan::from_actions(&Position(0), actions), plan);
+    }
+
+    #[test]
+    fn plan_cost_type() {
+        #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+        struct Position(u64);
+
+        #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+        enum Move {
+            Step,
+            Jump,
+        }
+
+        impl Action<Position, u64> for Move {
+            fn is_applicable(&self, _state: &Position) -> bool {
+                true
+            }
+
+            fn apply_mut(&self, state: &mut Position) {
+                state.0 += match self {
+                    Move::Step => 1,
+                    Move::Jump => 3,
+                };
+            }
+
+            fn cost(&self, _state: &Position) -> u64 {
+                match self {
+                    Move::Step => 1,
+                    Move::Jump => 10,
+                }
+            }
+        }
+
+        #[derive(PartialEq, Eq, Hash, Clone)]
+        struct Reach(u64);
+
+        impl Goal<Position, u64> for Reach {
+            fn is_satisfied(&self, state: &Position) -> bool {
+                state.0 == self.0
+            }
+        }
+
+        let actions = [Move::Step, Move::Jump];
+        let (path, cost) = plan(&Position(0), &actions, &Reach(3)).unwrap();
+        assert_eq!(path, vec![Move::Step; 3]);
+        assert_eq!(cost, 3u64);
+
+        let budget = SearchBudget::unlimited().with_max_cost(2u64);
+        let result = try_plan(&Position(0), &actions, &Reach(3), &budget);
+        assert!(matches!(result, Err(PlanError::BudgetExhausted(_))));
+    }
 }
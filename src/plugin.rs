@@ -0,0 +1,1087 @@
+use crate::{
+    first_error, run_sensor, Action, ActionStatus, Agent, Cost, ExecutorEvent, Goal, Invalidation,
+    Plan, PlanError, PlannerTask, SearchBudget, SensedAgents, Sensor, SensorSystems,
+};
+use bevy::app::{App, Plugin, Update};
+use bevy::ecs::prelude::*;
+use bevy::ecs::system::{EntityCommands, StaticSystemParam};
+use bevy::tasks::futures_lite::future;
+use bevy::tasks::AsyncComputeTaskPool;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::mem;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::Poll;
+
+/// Plans for every entity with an `Agent<S, A, G>` component, searching on Bevy's `AsyncComputeTaskPool`.
+///
+/// Each frame, agents without a plan which have an unsatisfied goal start searching for one,
+/// choosing the goal with the highest priority like `Agent::try_plan_dynamic`.
+/// While searching, the entity has a `PendingPlan` component, and once a plan is found it is stored in a `CurrentPlan`.
+/// Searches are restarted from the agent's current state whenever the agent changes, cancelling the previous one.
+///
+/// The pool must be initialized, for example by adding Bevy's `TaskPoolPlugin` before this plugin.
+/// Without Bevy's `multi_threaded` feature, the pool runs each search to completion as soon as it starts.
+///
+/// Plans are checked every frame, like an `Executor` does: the plan is discarded once its goal is satisfied,
+/// when the current action is no longer applicable, or when the agent's state differs from the state
+/// the plan expected at the current step.
+///
+/// ## Performing actions
+/// Marker components can be registered for actions with `with_marker`. While an action is the current step
+/// of an agent's plan, its marker is inserted on the entity, so game systems can perform it by querying for the marker.
+/// Once the agent's state reflects the action, they send an `ActionOutcome` which succeeded,
+/// or one which failed to discard the plan. Calling `CurrentPlan::advance` directly also moves on to the next step.
+///
+/// Everything that happens is reported as a `PlanningEvent`. If no plan can be found,
+/// the entity is marked with `PlanningFailed` until its agent changes.
+///
+/// Sensors registered with `with_sensor` keep the agents' states up to date with the rest of the ECS.
+///
+/// The systems run in `Update`, in the `PlanningSystems` set, after sensors in the `SensorSystems` set.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+/// use bevy::app::App;
+/// use bevy::core::TaskPoolPlugin;
+/// use bevy::ecs::event::Events;
+///
+/// #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// struct Increment;
+///
+/// impl Action<u32> for Increment {
+///     fn is_applicable(&self, state: &u32) -> bool {
+///         *state < 10
+///     }
+///
+///     fn apply_mut(&self, state: &mut u32) {
+///         *state += 1;
+///     }
+/// }
+///
+/// #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// struct Reach(u32);
+///
+/// impl Goal<u32> for Reach {
+///     fn is_satisfied(&self, state: &u32) -> bool {
+///         *state == self.0
+///     }
+/// }
+///
+/// type MyAgent = Agent<u32, Increment, Reach>;
+///
+/// let mut app = App::new();
+/// app.add_plugins(TaskPoolPlugin::default())
+///     .add_plugins(PlanningPlugin::<u32, Increment, Reach>::new().with_max_searches_per_frame(8));
+/// let entity = app
+///     .world_mut()
+///     .spawn(Agent::new(0, vec![Increment], vec![Reach(3)]))
+///     .id();
+///
+/// while app.world().get::<CurrentPlan<u32, Increment, Reach>>(entity).is_none() {
+///     app.update();
+/// }
+/// let plan = app.world().get::<CurrentPlan<u32, Increment, Reach>>(entity).unwrap();
+/// assert_eq!(plan.goal(), &Reach(3));
+/// assert_eq!(plan.action(), Some(&Increment));
+/// assert_eq!(plan.plan().len(), 3);
+///
+/// let events = app.world().resource::<Events<PlanningEvent<u32, Reach>>>();
+/// let found: Vec<_> = events.get_reader().read(events).cloned().collect();
+/// assert_eq!(
+///     found,
+///     vec![PlanningEvent { entity, event: ExecutorEvent::PlanFound(Reach(3)) }]
+/// );
+///
+/// // Performing the plan completes it
+/// for _ in 0..3 {
+///     app.world_mut().get_mut::<MyAgent>(entity).unwrap().state += 1;
+///     let mut plan = app.world_mut().get_mut::<CurrentPlan<u32, Increment, Reach>>(entity).unwrap();
+///     plan.advance();
+/// }
+/// app.update();
+/// assert!(app.world().get::<CurrentPlan<u32, Increment, Reach>>(entity).is_none());
+/// ```
+pub struct PlanningPlugin<S, A, G, C = i32> {
+    budget: SearchBudget<C>,
+    max_searches_per_frame: usize,
+    check_divergence: bool,
+    markers: Vec<Arc<ActionMarker<A>>>,
+    sensors: Vec<AddSensor>,
+    _agent: AgentTypes<S, A, G>,
+}
+
+/// Adds the system running a sensor to an app.
+type AddSensor = Arc<dyn Fn(&mut App) + Send + Sync>;
+
+/// The types of the agents planned for, without storing any.
+type AgentTypes<S, A, G> = PhantomData<fn() -> (S, A, G)>;
+
+impl<S, A, G, C> PlanningPlugin<S, A, G, C>
+where
+    C: Cost,
+{
+    /// Creates a plugin which starts one search each frame, without a budget, and checks for divergence.
+    pub fn new() -> Self {
+        Self {
+            budget: SearchBudget::unlimited(),
+            max_searches_per_frame: 1,
+            check_divergence: true,
+            markers: Vec::new(),
+            sensors: Vec::new(),
+            _agent: PhantomData,
+        }
+    }
+
+    /// Sets the budget for each search.
+    ///
+    /// A deadline is an absolute point in time, so it applies to all searches.
+    /// Limiting the number of states expanded or the time of each search is usually more useful.
+    pub fn with_budget(mut self, budget: SearchBudget<C>) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Sets the maximum number of searches started each frame.
+    ///
+    /// Agents which can't start searching are left for the following frames.
+    pub fn with_max_searches_per_frame(mut self, max_searches_per_frame: usize) -> Self {
+        self.max_searches_per_frame = max_searches_per_frame;
+        self
+    }
+
+    /// Sets whether the agent's state must match the state expected by the plan.
+    ///
+    /// If false, plans are only discarded when their current action isn't applicable.
+    pub fn with_check_divergence(mut self, check_divergence: bool) -> Self {
+        self.check_divergence = check_divergence;
+        self
+    }
+
+    /// Registers a marker component for the actions it is returned for.
+    ///
+    /// The marker is inserted when the action becomes the current step of an agent's plan,
+    /// and removed once the plan moves on or is discarded. Consecutive actions with the same type of marker,
+    /// such as moving to one point then another, replace the marker so that it is added again.
+    ///
+    /// # Example
+    /// ```
+    /// # use planning::*;
+    /// use bevy::app::{App, Update};
+    /// use bevy::core::TaskPoolPlugin;
+    /// use bevy::ecs::prelude::*;
+    ///
+    /// #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    /// enum Move {
+    ///     Left,
+    ///     Right,
+    /// }
+    ///
+    /// impl Action<i32> for Move {
+    ///     fn is_applicable(&self, state: &i32) -> bool {
+    ///         state.abs() < 10
+    ///     }
+    ///
+    ///     fn apply_mut(&self, state: &mut i32) {
+    ///         *state += match self {
+    ///             Move::Left => -1,
+    ///             Move::Right => 1,
+    ///         };
+    ///     }
+    /// }
+    ///
+    /// #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    /// struct Reach(i32);
+    ///
+    /// impl Goal<i32> for Reach {
+    ///     fn is_satisfied(&self, state: &i32) -> bool {
+    ///         *state == self.0
+    ///     }
+    /// }
+    ///
+    /// #[derive(Component)]
+    /// struct Walking(i32);
+    ///
+    /// // Performs the current step of each walking agent
+    /// fn walk(
+    ///     mut agents: Query<(Entity, &mut Agent<i32, Move, Reach>, &Walking)>,
+    ///     mut outcomes: EventWriter<ActionOutcome>,
+    /// ) {
+    ///     for (entity, mut agent, walking) in &mut agents {
+    ///         agent.state += walking.0;
+    ///         outcomes.send(ActionOutcome { entity, status: ActionStatus::Succeeded });
+    ///     }
+    /// }
+    ///
+    /// let plugin = PlanningPlugin::<i32, Move, Reach>::new().with_marker(|action| {
+    ///     Some(match action {
+    ///         Move::Left => Walking(-1),
+    ///         Move::Right => Walking(1),
+    ///     })
+    /// });
+    /// let mut app = App::new();
+    /// app.add_plugins((TaskPoolPlugin::default(), plugin))
+    ///     .add_systems(Update, walk.before(PlanningSystems));
+    /// let entity = app
+    ///     .world_mut()
+    ///     .spawn(Agent::new(0, vec![Move::Left, Move::Right], vec![Reach(-3)]))
+    ///     .id();
+    ///
+    /// for _ in 0..20 {
+    ///     app.update();
+    /// }
+    /// assert_eq!(app.world().get::<Agent<i32, Move, Reach>>(entity).unwrap().state, -3);
+    /// assert!(app.world().get::<Walking>(entity).is_none());
+    /// ```
+    pub fn with_marker<M>(
+        mut self,
+        marker: impl Fn(&A) -> Option<M> + Send + Sync + 'static,
+    ) -> Self
+    where
+        M: Component,
+    {
+        self.markers.push(Arc::new(ActionMarker {
+            insert: Box::new(move |action, entity| {
+                if let Some(marker) = marker(action) {
+                    entity.insert(marker);
+                }
+            }),
+            remove: Box::new(|entity| {
+                entity.remove::<M>();
+            }),
+        }));
+        self
+    }
+
+    /// Registers a sensor, which writes to the state of every agent each frame. See `Sensor` for an example.
+    pub fn with_sensor<T>(mut self, sensor: T) -> Self
+    where
+        S: Clone + Hash + Eq + Send + Sync + 'static,
+        A: Send + Sync + 'static,
+        G: Send + Sync + 'static,
+        C: 'static,
+        T: Sensor<S>,
+    {
+        let sensor = Arc::new(sensor);
+        self.sensors.push(Arc::new(move |app: &mut App| {
+            let sensor = sensor.clone();
+            let system = move |param: StaticSystemParam<T::Param>,
+                               mut agents: SensedAgents<S, A, G>| {
+                run_sensor(&*sensor, &param, &mut agents);
+            };
+            app.add_systems(Update, system.in_set(SensorSystems));
+        }));
+        self
+    }
+}
+
+impl<S, A, G, C> Default for PlanningPlugin<S, A, G, C>
+where
+    C: Cost,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S, A, G, C> Plugin for PlanningPlugin<S, A, G, C>
+where
+    S: Clone + Hash + Eq + Send + Sync + 'static,
+    A: Action<S, C> + Eq + Clone + Hash + Send + Sync + 'static,
+    G: Goal<S, C> + Clone + Send + Sync + 'static,
+    C: Cost + Send + Sync + 'static,
+{
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PlanningSettings::<S, A, G, C> {
+            budget: self.budget,
+            max_searches_per_frame: self.max_searches_per_frame,
+            check_divergence: self.check_divergence,
+            markers: self.markers.clone(),
+            _agent: PhantomData,
+        })
+        .add_event::<PlanningEvent<S, G>>()
+        .add_event::<ActionOutcome>()
+        .configure_sets(Update, SensorSystems.before(PlanningSystems))
+        .add_systems(
+            Update,
+            (
+                retry_failed::<S, A, G>,
+                apply_outcomes::<S, A, G, C>,
+                check_plans::<S, A, G, C>,
+                start_searches::<S, A, G, C>,
+                receive_plans::<S, A, G, C>,
+                dispatch_markers::<S, A, G, C>,
+            )
+                .chain()
+                .in_set(PlanningSystems),
+        );
+        for add_sensor in &self.sensors {
+            add_sensor(app);
+        }
+    }
+}
+
+/// The systems added by `PlanningPlugin`, which run in `Update`.
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PlanningSystems;
+
+/// Something that happened while planning for an entity's agent.
+#[derive(Event, Clone, Debug, PartialEq, Eq)]
+pub struct PlanningEvent<S, G> {
+    /// The entity of the agent.
+    pub entity: Entity,
+    /// What happened.
+    pub event: ExecutorEvent<S, G>,
+}
+
+/// Reports the progress of the current action of an entity's agent, sent by the systems performing it.
+///
+/// An action which succeeded moves the plan on to its next step, and one which failed discards the plan.
+/// Outcomes which are still running are ignored.
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ActionOutcome {
+    /// The entity of the agent.
+    pub entity: Entity,
+    /// Whether the action is still running, succeeded or failed.
+    pub status: ActionStatus,
+}
+
+/// The plan an agent is following, added by `PlanningPlugin` once a plan is found.
+#[derive(Component, Clone, Debug)]
+pub struct CurrentPlan<S, A, G, C = i32>
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    G: Goal<S, C> + Clone,
+    C: Cost,
+{
+    goal: G,
+    plan: Plan<S, A, C>,
+    step: usize,
+    /// The step whose marker is inserted on the entity.
+    dispatched: Option<usize>,
+    invalidation: Option<Invalidation>,
+}
+
+impl<S, A, G, C> CurrentPlan<S, A, G, C>
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    G: Goal<S, C> + Clone,
+    C: Cost,
+{
+    /// Returns the goal the plan satisfies.
+    pub fn goal(&self) -> &G {
+        &self.goal
+    }
+
+    /// Returns the plan.
+    pub fn plan(&self) -> &Plan<S, A, C> {
+        &self.plan
+    }
+
+    /// Returns the index of the current step in the plan.
+    pub fn step_index(&self) -> usize {
+        self.step
+    }
+
+    /// Returns the action which should currently be performed, if any steps are left.
+    pub fn action(&self) -> Option<&A> {
+        self.plan.steps().get(self.step).map(|step| &step.action)
+    }
+
+    /// Moves on to the next step, once the agent's state reflects the current action.
+    pub fn advance(&mut self) {
+        self.step = (self.step + 1).min(self.plan.len());
+    }
+
+    /// Discards the plan, so that a new one is searched for.
+    pub fn invalidate(&mut self) {
+        self.invalidation = Some(Invalidation::Manual);
+    }
+
+    /// Returns the reason the plan should be discarded in the given state, if any.
+    fn invalidation(&self, state: &S, check_divergence: bool) -> Option<Invalidation> {
+        if self.invalidation.is_some() {
+            return self.invalidation;
+        }
+        let Some(step) = self.plan.steps().get(self.step) else {
+            // Every step was performed without satisfying the goal
+            return Some(Invalidation::Diverged);
+        };
+        if check_divergence && step.pre_state != *state {
+            Some(Invalidation::Diverged)
+        } else if !step.action.is_applicable(state) {
+            Some(Invalidation::NotApplicable)
+        } else {
+            None
+        }
+    }
+}
+
+/// The search for an agent's plan, running on the `AsyncComputeTaskPool` until it finishes.
+///
+/// Removing it cancels the search.
+#[derive(Component)]
+pub struct PendingPlan<S, A, G, C = i32> {
+    /// The handle of the task running the search, which cancels it once dropped on multi-threaded platforms.
+    _task: Box<dyn Send + Sync>,
+    /// Stops the search where dropping the task doesn't.
+    cancelled: Arc<AtomicBool>,
+    result: SearchSlot<S, A, G, C>,
+}
+
+/// The number of states a search expands before letting other tasks run.
+const STATES_PER_YIELD: usize = 256;
+
+impl<S, A, G, C> PendingPlan<S, A, G, C>
+where
+    S: Clone + Hash + Eq + Send + Sync + 'static,
+    A: Action<S, C> + Eq + Clone + Hash + Send + Sync + 'static,
+    G: Goal<S, C> + Clone + Send + Sync + 'static,
+    C: Cost + Send + Sync + 'static,
+{
+    /// Starts searching for the agent's goals on the `AsyncComputeTaskPool`.
+    fn spawn(agent: Agent<S, A, G>, budget: SearchBudget<C>) -> Self {
+        let mut search = GoalSearch::new(agent, budget);
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let result = Arc::new(Mutex::new(None));
+        let (stop, sender) = (cancelled.clone(), result.clone());
+        let task = AsyncComputeTaskPool::get().spawn(async move {
+            while !stop.load(Ordering::Relaxed) {
+                if let Poll::Ready(found) = search.step(STATES_PER_YIELD) {
+                    *sender.lock().unwrap() = Some(found);
+                    return;
+                }
+                future::yield_now().await;
+            }
+        });
+        Self {
+            _task: Box::new(task),
+            cancelled,
+            result,
+        }
+    }
+}
+
+impl<S, A, G, C> Drop for PendingPlan<S, A, G, C> {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+type SearchResult<S, A, G, C> = Result<(G, Plan<S, A, C>), PlanError<S>>;
+
+/// Where a search stores its result once it finishes.
+type SearchSlot<S, A, G, C> = Arc<Mutex<Option<SearchResult<S, A, G, C>>>>;
+
+/// Searches for an agent's goals in order of priority, like `Agent::try_plan_dynamic`.
+struct GoalSearch<S, A, G, C>
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    G: Goal<S, C> + Clone,
+    C: Cost,
+{
+    /// The agent as the search started, with its goals sorted by priority.
+    agent: Agent<S, A, G>,
+    budget: SearchBudget<C>,
+    /// The index of the goal currently searched for.
+    goal: usize,
+    task: PlannerTask<S, A, G, C>,
+    /// The states expanded by the searches for previous goals.
+    expanded: usize,
+    /// The reasons previous goals couldn't be planned for.
+    errors: Vec<PlanError<S>>,
+}
+
+impl<S, A, G, C> GoalSearch<S, A, G, C>
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    G: Goal<S, C> + Clone,
+    C: Cost,
+{
+    fn new(mut agent: Agent<S, A, G>, budget: SearchBudget<C>) -> Self {
+        agent.sort_goals();
+        let task = Self::task(&agent, 0, budget);
+        Self {
+            agent,
+            budget,
+            goal: 0,
+            task,
+            expanded: 0,
+            errors: Vec::new(),
+        }
+    }
+
+    fn task(
+        agent: &Agent<S, A, G>,
+        goal: usize,
+        budget: SearchBudget<C>,
+    ) -> PlannerTask<S, A, G, C> {
+        PlannerTask::with_budget(
+            agent.state.clone(),
+            agent.actions.clone(),
+            agent.goals[goal].clone(),
+            budget,
+        )
+    }
+
+    /// Advances the search for the current goal, expanding at most `max_expanded` states.
+    ///
+    /// Once the goal can't be planned for, the search moves on to the next one.
+    fn step(&mut self, max_expanded: usize) -> Poll<SearchResult<S, A, G, C>> {
+        let Poll::Ready(result) = self.task.step(max_expanded) else {
+            return Poll::Pending;
+        };
+        match result {
+            Ok(plan) => return Poll::Ready(Ok((self.task.goal().clone(), plan))),
+            Err(error @ PlanError::BudgetExhausted(_)) => return Poll::Ready(Err(error)),
+            Err(error) => self.errors.push(error),
+        }
+        self.expanded += self.task.expanded();
+        self.goal += 1;
+        if self.goal == self.agent.goals.len() {
+            return Poll::Ready(Err(first_error(mem::take(&mut self.errors))));
+        }
+        self.task = Self::task(&self.agent, self.goal, self.budget.after(self.expanded));
+        Poll::Pending
+    }
+}
+
+/// Marks an agent for which no plan could be found, so that it isn't searched for again until the agent changes.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PlanningFailed;
+
+#[derive(Resource)]
+struct PlanningSettings<S, A, G, C> {
+    budget: SearchBudget<C>,
+    max_searches_per_frame: usize,
+    check_divergence: bool,
+    markers: Vec<Arc<ActionMarker<A>>>,
+    _agent: AgentTypes<S, A, G>,
+}
+
+/// Inserts the marker component registered for an action, and removes it.
+struct ActionMarker<A> {
+    insert: InsertMarker<A>,
+    remove: Box<dyn Fn(&mut EntityCommands) + Send + Sync>,
+}
+
+type InsertMarker<A> = Box<dyn Fn(&A, &mut EntityCommands) + Send + Sync>;
+
+/// Agents which failed to plan and changed since.
+type ChangedFailedAgents<'w, 's, S, A, G> =
+    Query<'w, 's, Entity, (With<PlanningFailed>, Changed<Agent<S, A, G>>)>;
+
+/// Agents following a plan.
+type PlannedAgents<'w, 's, S, A, G, C> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Agent<S, A, G>,
+        &'static CurrentPlan<S, A, G, C>,
+    ),
+>;
+
+/// Agents which may need a plan, and whether they are searching for one.
+type UnplannedAgents<'w, 's, S, A, G, C> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        Ref<'static, Agent<S, A, G>>,
+        Has<PendingPlan<S, A, G, C>>,
+    ),
+    Unplanned<S, A, G, C>,
+>;
+
+/// Filters out agents which have a plan, or failed to find one.
+type Unplanned<S, A, G, C> = (Without<CurrentPlan<S, A, G, C>>, Without<PlanningFailed>);
+
+/// Agents searching for a plan.
+type Searches<'w, 's, S, A, G, C> = Query<'w, 's, (Entity, &'static PendingPlan<S, A, G, C>)>;
+
+/// The plans agents are following.
+type Plans<'w, 's, S, A, G, C> = Query<'w, 's, (Entity, &'static mut CurrentPlan<S, A, G, C>)>;
+
+/// Moves plans on or discards them as their actions are reported to finish.
+fn apply_outcomes<S, A, G, C>(
+    mut outcomes: EventReader<ActionOutcome>,
+    mut plans: Query<&mut CurrentPlan<S, A, G, C>>,
+) where
+    S: Clone + Hash + Eq + Send + Sync + 'static,
+    A: Action<S, C> + Eq + Clone + Hash + Send + Sync + 'static,
+    G: Goal<S, C> + Clone + Send + Sync + 'static,
+    C: Cost + Send + Sync + 'static,
+{
+    for outcome in outcomes.read() {
+        let Ok(mut plan) = plans.get_mut(outcome.entity) else {
+            continue;
+        };
+        match outcome.status {
+            ActionStatus::Running => {}
+            ActionStatus::Succeeded => plan.advance(),
+            ActionStatus::Failed => {
+                plan.invalidation.get_or_insert(Invalidation::Failed);
+            }
+        }
+    }
+}
+
+/// Allows agents to search again once they change after failing.
+fn retry_failed<S, A, G>(mut commands: Commands, agents: ChangedFailedAgents<S, A, G>)
+where
+    S: Send + Sync + 'static,
+    A: Send + Sync + 'static,
+    G: Send + Sync + 'static,
+{
+    for entity in &agents {
+        commands.entity(entity).remove::<PlanningFailed>();
+    }
+}
+
+/// Discards plans which are completed or can no longer be followed.
+fn check_plans<S, A, G, C>(
+    mut commands: Commands,
+    settings: Res<PlanningSettings<S, A, G, C>>,
+    agents: PlannedAgents<S, A, G, C>,
+    mut events: EventWriter<PlanningEvent<S, G>>,
+) where
+    S: Clone + Hash + Eq + Send + Sync + 'static,
+    A: Action<S, C> + Eq + Clone + Hash + Send + Sync + 'static,
+    G: Goal<S, C> + Clone + Send + Sync + 'static,
+    C: Cost + Send + Sync + 'static,
+{
+    for (entity, agent, current) in &agents {
+        let event = if current.goal.is_satisfied(&agent.state) {
+            ExecutorEvent::PlanCompleted(current.goal.clone())
+        } else if let Some(reason) = current.invalidation(&agent.state, settings.check_divergence) {
+            ExecutorEvent::PlanInvalidated(reason)
+        } else {
+            continue;
+        };
+        commands.entity(entity).remove::<CurrentPlan<S, A, G, C>>();
+        events.send(PlanningEvent { entity, event });
+    }
+}
+
+/// Starts searching for agents which have no plan and an unsatisfied goal.
+///
+/// Agents which changed while searching search again from their current state,
+/// as the plan being searched for could no longer be followed.
+fn start_searches<S, A, G, C>(
+    mut commands: Commands,
+    settings: Res<PlanningSettings<S, A, G, C>>,
+    agents: UnplannedAgents<S, A, G, C>,
+) where
+    S: Clone + Hash + Eq + Send + Sync + 'static,
+    A: Action<S, C> + Eq + Clone + Hash + Send + Sync + 'static,
+    G: Goal<S, C> + Clone + Send + Sync + 'static,
+    C: Cost + Send + Sync + 'static,
+{
+    let mut started = 0;
+    for (entity, agent, pending) in &agents {
+        if pending && !agent.is_changed() {
+            continue;
+        }
+        let has_unsatisfied_goal = agent
+            .goals
+            .iter()
+            .any(|goal| !goal.is_satisfied(&agent.state));
+        if has_unsatisfied_goal && started < settings.max_searches_per_frame {
+            let search = PendingPlan::spawn(agent.clone(), settings.budget);
+            commands.entity(entity).insert(search);
+            started += 1;
+        } else if pending {
+            commands.entity(entity).remove::<PendingPlan<S, A, G, C>>();
+        }
+    }
+}
+
+/// Stores the results of finished searches.
+fn receive_plans<S, A, G, C>(
+    mut commands: Commands,
+    searches: Searches<S, A, G, C>,
+    mut events: EventWriter<PlanningEvent<S, G>>,
+) where
+    S: Clone + Hash + Eq + Send + Sync + 'static,
+    A: Action<S, C> + Eq + Clone + Hash + Send + Sync + 'static,
+    G: Goal<S, C> + Clone + Send + Sync + 'static,
+    C: Cost + Send + Sync + 'static,
+{
+    for (entity, search) in &searches {
+        let Some(result) = search.result.lock().unwrap().take() else {
+            continue;
+        };
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.remove::<PendingPlan<S, A, G, C>>();
+        let event = match result {
+            Ok((goal, plan)) => {
+                entity_commands.insert(CurrentPlan {
+                    goal: goal.clone(),
+                    plan,
+                    step: 0,
+                    dispatched: None,
+                    invalidation: None,
+                });
+                ExecutorEvent::PlanFound(goal)
+            }
+            // Every goal is satisfied
+            Err(PlanError::AlreadySatisfied) => continue,
+            Err(error) => {
+                entity_commands.insert(PlanningFailed);
+                ExecutorEvent::PlanFailed(error)
+            }
+        };
+        events.send(PlanningEvent { entity, event });
+    }
+}
+
+/// Inserts the marker of each agent's current action, removing the markers of previous actions.
+fn dispatch_markers<S, A, G, C>(
+    mut commands: Commands,
+    settings: Res<PlanningSettings<S, A, G, C>>,
+    mut plans: Plans<S, A, G, C>,
+    mut discarded: RemovedComponents<CurrentPlan<S, A, G, C>>,
+) where
+    S: Clone + Hash + Eq + Send + Sync + 'static,
+    A: Action<S, C> + Eq + Clone + Hash + Send + Sync + 'static,
+    G: Goal<S, C> + Clone + Send + Sync + 'static,
+    C: Cost + Send + Sync + 'static,
+{
+    for entity in discarded.read() {
+        if let Some(mut entity_commands) = commands.get_entity(entity) {
+            for marker in &settings.markers {
+                (marker.remove)(&mut entity_commands);
+            }
+        }
+    }
+    for (entity, mut plan) in &mut plans {
+        if plan.dispatched == Some(plan.step) {
+            continue;
+        }
+        plan.dispatched = Some(plan.step);
+        let mut entity_commands = commands.entity(entity);
+        for marker in &settings.markers {
+            (marker.remove)(&mut entity_commands);
+        }
+        if let Some(action) = plan.action() {
+            for marker in &settings.markers {
+                (marker.insert)(action, &mut entity_commands);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::core::TaskPoolPlugin;
+    use bevy::ecs::event::Events;
+
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    enum Step {
+        Forward,
+        Back,
+    }
+
+    impl Action<i32> for Step {
+        fn is_applicable(&self, state: &i32) -> bool {
+            match self {
+                Step::Forward => *state < 5,
+                Step::Back => *state > -5,
+            }
+        }
+
+        fn apply_mut(&self, state: &mut i32) {
+            match self {
+                Step::Forward => *state += 1,
+                Step::Back => *state -= 1,
+            }
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    struct Reach(i32);
+
+    impl Goal<i32> for Reach {
+        fn is_satisfied(&self, state: &i32) -> bool {
+            *state == self.0
+        }
+    }
+
+    type TestAgent = Agent<i32, Step, Reach>;
+    type TestPlan = CurrentPlan<i32, Step, Reach>;
+
+    fn app(plugin: PlanningPlugin<i32, Step, Reach>) -> App {
+        let mut app = App::new();
+        app.add_plugins((TaskPoolPlugin::default(), plugin));
+        app
+    }
+
+    /// Updates the app until no searches are pending or sending events, returning the events sent meanwhile.
+    fn settle(app: &mut App) -> Vec<PlanningEvent<i32, Reach>> {
+        let mut reader = app
+            .world()
+            .resource::<Events<PlanningEvent<i32, Reach>>>()
+            .get_reader_current();
+        let mut sent = Vec::new();
+        for _ in 0..100 {
+            app.update();
+            let events = app.world().resource::<Events<PlanningEvent<i32, Reach>>>();
+            let count = sent.len();
+            sent.extend(reader.read(events).cloned());
+            let mut pending = app.world_mut().query::<&PendingPlan<i32, Step, Reach>>();
+            if pending.iter(app.world()).next().is_none() && sent.len() == count {
+                return sent;
+            }
+        }
+        panic!("searches did not finish");
+    }
+
+    #[test]
+    fn plugin_lifecycle() {
+        let mut app = app(PlanningPlugin::new());
+        let entity = app
+            .world_mut()
+            .spawn(TestAgent::new(
+                0,
+                vec![Step::Forward, Step::Back],
+                vec![Reach(2)],
+            ))
+            .id();
+
+        let events = settle(&mut app);
+        assert_eq!(
+            events,
+            vec![PlanningEvent {
+                entity,
+                event: ExecutorEvent::PlanFound(Reach(2)),
+            }]
+        );
+        let plan = app.world().get::<TestPlan>(entity).unwrap();
+        assert_eq!(plan.action(), Some(&Step::Forward));
+
+        // Something else moved the agent, so the plan is replaced
+        app.world_mut().get_mut::<TestAgent>(entity).unwrap().state = -1;
+        let events = settle(&mut app);
+        let kinds: Vec<_> = events.into_iter().map(|event| event.event).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ExecutorEvent::PlanInvalidated(Invalidation::Diverged),
+                ExecutorEvent::PlanFound(Reach(2)),
+            ]
+        );
+        assert_eq!(app.world().get::<TestPlan>(entity).unwrap().plan().len(), 3);
+
+        app.world_mut()
+            .get_mut::<TestPlan>(entity)
+            .unwrap()
+            .invalidate();
+        let events = settle(&mut app);
+        assert_eq!(
+            events[0].event,
+            ExecutorEvent::PlanInvalidated(Invalidation::Manual)
+        );
+
+        app.world_mut().get_mut::<TestAgent>(entity).unwrap().state = 2;
+        let events = settle(&mut app);
+        assert_eq!(events[0].event, ExecutorEvent::PlanCompleted(Reach(2)));
+        assert!(app.world().get::<TestPlan>(entity).is_none());
+        assert!(app
+            .world()
+            .get::<PendingPlan<i32, Step, Reach>>(entity)
+            .is_none());
+    }
+
+    #[test]
+    fn plugin_failures_and_limits() {
+        let mut app = app(PlanningPlugin::new()
+            .with_budget(SearchBudget::unlimited().with_max_expanded(100))
+            .with_check_divergence(false));
+        let stuck = app
+            .world_mut()
+            .spawn(TestAgent::new(0, vec![Step::Forward], vec![Reach(-3)]))
+            .id();
+        let others: Vec<_> = (0..3)
+            .map(|_| {
+                app.world_mut()
+                    .spawn(TestAgent::new(0, vec![Step::Forward], vec![Reach(4)]))
+                    .id()
+            })
+            .collect();
+
+        // One search starts each frame
+        let mut reader = app
+            .world()
+            .resource::<Events<PlanningEvent<i32, Reach>>>()
+            .get_reader_current();
+        app.update();
+        let mut started = app.world_mut().query_filtered::<(), Or<(
+            With<PendingPlan<i32, Step, Reach>>,
+            With<TestPlan>,
+            With<PlanningFailed>,
+        )>>();
+        assert_eq!(started.iter(app.world()).count(), 1);
+
+        let events = app.world().resource::<Events<PlanningEvent<i32, Reach>>>();
+        let mut events: Vec<_> = reader.read(events).cloned().collect();
+        events.extend(settle(&mut app));
+        let failed: Vec<_> = events
+            .iter()
+            .filter(|event| matches!(event.event, ExecutorEvent::PlanFailed(_)))
+            .map(|event| event.entity)
+            .collect();
+        assert_eq!(failed, vec![stuck]);
+        assert!(app.world().get::<PlanningFailed>(stuck).is_some());
+        for entity in others {
+            assert!(app.world().get::<TestPlan>(entity).is_some());
+        }
+
+        // Failed agents aren't searched for again until they change
+        assert!(settle(&mut app).is_empty());
+        app.world_mut()
+            .get_mut::<TestAgent>(stuck)
+            .unwrap()
+            .actions
+            .push(Step::Back);
+        let events = settle(&mut app);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event, ExecutorEvent::PlanFound(Reach(-3)));
+    }
+
+    #[derive(Component, Debug, PartialEq)]
+    struct Stepping(i32);
+
+    #[derive(Component)]
+    struct Retreating;
+
+    #[test]
+    fn plugin_markers() {
+        let plugin = PlanningPlugin::new()
+            .with_marker(|step| {
+                Some(match step {
+                    Step::Forward => Stepping(1),
+                    Step::Back => Stepping(-1),
+                })
+            })
+            .with_marker(|step| (*step == Step::Back).then_some(Retreating));
+        let mut app = app(plugin);
+        let entity = app
+            .world_mut()
+            .spawn(TestAgent::new(
+                0,
+                vec![Step::Forward, Step::Back],
+                vec![Reach(2)],
+            ))
+            .id();
+
+        settle(&mut app);
+        assert_eq!(app.world().get::<Stepping>(entity), Some(&Stepping(1)));
+        assert!(app.world().get::<Retreating>(entity).is_none());
+
+        app.world_mut().get_mut::<TestAgent>(entity).unwrap().state = 1;
+        app.world_mut().send_event(ActionOutcome {
+            entity,
+            status: ActionStatus::Succeeded,
+        });
+        app.update();
+        let plan = app.world().get::<TestPlan>(entity).unwrap();
+        assert_eq!(plan.step_index(), 1);
+        assert_eq!(app.world().get::<Stepping>(entity), Some(&Stepping(1)));
+
+        // A failed action discards the plan and its markers
+        let mut reader = app
+            .world()
+            .resource::<Events<PlanningEvent<i32, Reach>>>()
+            .get_reader_current();
+        app.world_mut().send_event(ActionOutcome {
+            entity,
+            status: ActionStatus::Failed,
+        });
+        app.update();
+        let events = app.world().resource::<Events<PlanningEvent<i32, Reach>>>();
+        let sent: Vec<_> = reader
+            .read(events)
+            .map(|event| event.event.clone())
+            .collect();
+        assert_eq!(
+            sent,
+            vec![
+                ExecutorEvent::PlanInvalidated(Invalidation::Failed),
+                ExecutorEvent::PlanFound(Reach(2)),
+            ]
+        );
+        let plan = app.world().get::<TestPlan>(entity).unwrap();
+        assert_eq!((plan.step_index(), plan.plan().len()), (0, 1));
+        assert_eq!(app.world().get::<Stepping>(entity), Some(&Stepping(1)));
+
+        // The new plan goes back from further along
+        app.world_mut().get_mut::<TestAgent>(entity).unwrap().state = 4;
+        let events = settle(&mut app);
+        assert!(events
+            .iter()
+            .any(|event| event.event == ExecutorEvent::PlanFound(Reach(2))));
+        assert_eq!(app.world().get::<Stepping>(entity), Some(&Stepping(-1)));
+        assert!(app.world().get::<Retreating>(entity).is_some());
+
+        // Completing the plan removes its markers
+        app.world_mut().get_mut::<TestAgent>(entity).unwrap().state = 2;
+        let events = settle(&mut app);
+        assert_eq!(events[0].event, ExecutorEvent::PlanCompleted(Reach(2)));
+        assert!(app.world().get::<Stepping>(entity).is_none());
+        assert!(app.world().get::<Retreating>(entity).is_none());
+    }
+
+    #[test]
+    fn plugin_restarts_searches() {
+        let mut app = app(PlanningPlugin::new());
+        let entity = app
+            .world_mut()
+            .spawn(TestAgent::new(
+                0,
+                vec![Step::Forward, Step::Back],
+                vec![Reach(3)],
+            ))
+            .id();
+        settle(&mut app);
+
+        // Replaces the plan with a search which never finishes
+        let cancelled = Arc::new(AtomicBool::new(false));
+        app.world_mut()
+            .entity_mut(entity)
+            .remove::<TestPlan>()
+            .insert(PendingPlan::<i32, Step, Reach> {
+                _task: Box::new(()),
+                cancelled: cancelled.clone(),
+                result: Arc::new(Mutex::new(None)),
+            });
+        app.update();
+        assert!(app
+            .world()
+            .get::<PendingPlan<i32, Step, Reach>>(entity)
+            .is_some());
+        assert!(!cancelled.load(Ordering::Relaxed));
+
+        // The agent moved while searching, so the search starts over from where it is
+        app.world_mut().get_mut::<TestAgent>(entity).unwrap().state = 1;
+        let events = settle(&mut app);
+        assert!(cancelled.load(Ordering::Relaxed));
+        assert_eq!(
+            events,
+            vec![PlanningEvent {
+                entity,
+                event: ExecutorEvent::PlanFound(Reach(3)),
+            }]
+        );
+        let plan = app.world().get::<TestPlan>(entity).unwrap();
+        assert_eq!(plan.plan().steps()[0].pre_state, 1);
+        assert_eq!(plan.plan().len(), 2);
+    }
+}
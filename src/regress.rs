@@ -0,0 +1,523 @@
+use crate::{check_initial, Action, Cost, Goal, Plan, PlanError, SearchBudget, SearchStats};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::Hash;
+
+/// Extends an action so that plans can be searched for backwards, from the goal towards the initial state.
+///
+/// Searching forwards considers every applicable action, even if it has nothing to do with the goal.
+/// Searching backwards only considers actions which help to satisfy the goal,
+/// which is much faster when only a few of many actions are relevant.
+///
+/// This requires describing what must be true for a goal to be satisfied, as a condition.
+/// Conditions are goals themselves, typically a partial state which leaves unimportant values unspecified.
+/// The `regress` method returns the condition which must hold before the action,
+/// so that the given condition holds after it.
+///
+/// Plans found backwards are checked by applying them forwards from the initial state,
+/// so `regress` only needs to be accurate for the plan to be found, not for it to be correct.
+///
+/// ## Cost
+/// The state an action is applied to is not known while searching backwards,
+/// so the search uses `regression_cost` instead of `cost`. It defaults to a constant value.
+/// The costs of the returned plan are computed with `cost`.
+///
+/// ## Heuristic
+/// The heuristic of a condition estimates the cost from the initial state to a state which satisfies it.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+///
+/// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+/// struct Camp {
+///     has_axe: bool,
+///     has_wood: bool,
+/// }
+///
+/// /// A condition on the camp, where `None` means any value.
+/// #[derive(PartialEq, Eq, Hash, Clone, Debug, Default)]
+/// struct Needs {
+///     has_axe: Option<bool>,
+///     has_wood: Option<bool>,
+/// }
+///
+/// impl Goal<Camp> for Needs {
+///     fn is_satisfied(&self, state: &Camp) -> bool {
+///         self.has_axe.is_none_or(|v| v == state.has_axe)
+///             && self.has_wood.is_none_or(|v| v == state.has_wood)
+///     }
+/// }
+///
+/// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+/// enum CampAction {
+///     GetAxe,
+///     ChopWood,
+/// }
+///
+/// impl Action<Camp> for CampAction {
+///     fn is_applicable(&self, state: &Camp) -> bool {
+///         match self {
+///             CampAction::GetAxe => !state.has_axe,
+///             CampAction::ChopWood => state.has_axe,
+///         }
+///     }
+///
+///     fn apply_mut(&self, state: &mut Camp) {
+///         match self {
+///             CampAction::GetAxe => state.has_axe = true,
+///             CampAction::ChopWood => state.has_wood = true,
+///         }
+///     }
+/// }
+///
+/// impl RegressiveAction<Camp> for CampAction {
+///     type Condition = Needs;
+///
+///     fn regress(&self, condition: &Needs) -> Option<Needs> {
+///         match self {
+///             // Relevant if the axe is needed, and requires not having one
+///             CampAction::GetAxe if condition.has_axe == Some(true) => Some(Needs {
+///                 has_axe: Some(false),
+///                 ..condition.clone()
+///             }),
+///             // Relevant if wood is needed, and requires an axe
+///             CampAction::ChopWood if condition.has_wood == Some(true) && condition.has_axe != Some(false) => {
+///                 Some(Needs {
+///                     has_axe: Some(true),
+///                     has_wood: None,
+///                 })
+///             }
+///             _ => None,
+///         }
+///     }
+/// }
+///
+/// let camp = Camp { has_axe: false, has_wood: false };
+/// let goal = Needs { has_wood: Some(true), ..Needs::default() };
+/// let (path, cost) = plan_backward(&camp, &[CampAction::GetAxe, CampAction::ChopWood], &goal).unwrap();
+/// assert_eq!(path, vec![CampAction::GetAxe, CampAction::ChopWood]);
+/// assert_eq!(cost, 2);
+/// ```
+pub trait RegressiveAction<S, C = i32>: Action<S, C>
+where
+    S: Clone + Hash + Eq,
+    C: Cost,
+{
+    /// A condition on states, which is satisfied by the states it describes.
+    type Condition: Goal<S, C> + Clone + Hash + Eq;
+
+    /// Returns the condition which must hold before this action, so that the given condition holds after it.
+    ///
+    /// Returns `None` if the action doesn't help to satisfy the condition, or makes it impossible.
+    fn regress(&self, condition: &Self::Condition) -> Option<Self::Condition>;
+
+    /// Returns the cost of the action used while searching backwards, when the state it is applied to is not known.
+    ///
+    /// Implementing this method is optional, and it will default to a constant value if not implemented.
+    fn regression_cost(&self, _condition: &Self::Condition) -> C {
+        C::ONE
+    }
+}
+
+/// A condition reached by the backward search, linked to the condition it was regressed from.
+struct RegressionNode<K, A, C> {
+    condition: K,
+    next: Option<(usize, A)>,
+    cost: C,
+    depth: usize,
+}
+
+/// Returns the plan and total cost to satisfy the goal condition, found by searching backwards from it.
+///
+/// This is the backward equivalent of `plan`.
+pub fn plan_backward<S, A, C>(
+    initial_state: &S,
+    actions: &[A],
+    goal: &A::Condition,
+) -> Option<(Vec<A>, C)>
+where
+    S: Clone + Hash + Eq,
+    A: RegressiveAction<S, C> + Eq + Clone + Hash,
+    C: Cost,
+{
+    match try_plan_backward(initial_state, actions, goal, &SearchBudget::unlimited()) {
+        Ok(plan) => {
+            let cost = plan.cost();
+            Some((plan.into_actions(), cost))
+        }
+        Err(PlanError::AlreadySatisfied) => Some((vec![], C::ZERO)),
+        Err(_) => None,
+    }
+}
+
+/// Returns the plan to satisfy the goal condition found by searching backwards from it within the budget,
+/// or the reason none was found.
+///
+/// Conditions are searched in order of their `regression_cost` plus their heuristic.
+/// Each candidate plan is applied forwards from the initial state, and discarded if it doesn't satisfy the goal.
+/// Chains of actions reaching a condition already reached more cheaply are only searched
+/// if the plans through the cheaper chain are discarded.
+///
+/// Search statistics describe conditions rather than states,
+/// so the deepest state reported on failure is the initial state.
+pub fn try_plan_backward<S, A, C>(
+    initial_state: &S,
+    actions: &[A],
+    goal: &A::Condition,
+    budget: &SearchBudget<C>,
+) -> Result<Plan<S, A, C>, PlanError<S>>
+where
+    S: Clone + Hash + Eq,
+    A: RegressiveAction<S, C> + Eq + Clone + Hash,
+    C: Cost,
+{
+    if let Some(error) = check_initial(initial_state, actions, goal) {
+        return Err(error);
+    }
+
+    let mut nodes: Vec<RegressionNode<A::Condition, A, C>> = vec![RegressionNode {
+        condition: goal.clone(),
+        next: None,
+        cost: C::ZERO,
+        depth: 0,
+    }];
+    // The cost each condition was expanded at, and the chains reaching it at least as expensively since.
+    // Those chains are only searched if a plan through the expanded one fails validation.
+    let mut closed: HashMap<A::Condition, C> = HashMap::new();
+    let mut deferred: HashMap<A::Condition, Vec<usize>> = HashMap::new();
+    let mut open = BinaryHeap::from([Reverse((goal.heuristic(initial_state), 0))]);
+    let mut expanded = 0;
+    let mut max_depth = 0;
+    let mut pruned = false;
+
+    while let Some(Reverse((estimate, index))) = open.pop() {
+        let node = &nodes[index];
+        if closed
+            .get(&node.condition)
+            .is_some_and(|&cost| cost <= node.cost)
+        {
+            deferred
+                .entry(node.condition.clone())
+                .or_default()
+                .push(index);
+            continue;
+        }
+        if node.condition.is_satisfied(initial_state) {
+            let mut path = Vec::new();
+            let mut current = index;
+            while let Some((next, action)) = &nodes[current].next {
+                path.push(action.clone());
+                current = *next;
+            }
+            if let Some(plan) = validate(initial_state, path, goal) {
+                return Ok(plan);
+            }
+            // Reopen the conditions along the chain, as other chains to them may still lead to a valid plan
+            let mut current = node.next.as_ref().map(|(next, _)| *next);
+            while let Some(chained) = current {
+                let condition = &nodes[chained].condition;
+                closed.remove(condition);
+                for other in deferred.remove(condition).unwrap_or_default() {
+                    let estimate = nodes[other].cost + condition.heuristic(initial_state);
+                    open.push(Reverse((estimate, other)));
+                }
+                current = nodes[chained].next.as_ref().map(|(next, _)| *next);
+            }
+            continue;
+        }
+        if budget.is_spent(expanded) {
+            open.push(Reverse((estimate, index)));
+            return Err(PlanError::BudgetExhausted(SearchStats {
+                expanded,
+                frontier: open.len(),
+                deepest_state: initial_state.clone(),
+                max_depth,
+            }));
+        }
+        expanded += 1;
+        closed.insert(node.condition.clone(), node.cost);
+
+        let (condition, cost, depth) = (node.condition.clone(), node.cost, node.depth + 1);
+        for action in actions {
+            let Some(previous) = action.regress(&condition) else {
+                continue;
+            };
+            let previous_cost = cost + action.regression_cost(&condition);
+            if !budget.allows(depth, previous_cost) {
+                pruned = true;
+                continue;
+            }
+            if on_chain(&nodes, index, &previous) {
+                continue; // Cycles never lead to cheaper plans, and reopening conditions could repeat them forever
+            }
+            let child = nodes.len();
+            if closed
+                .get(&previous)
+                .is_some_and(|&existing| existing <= previous_cost)
+            {
+                deferred.entry(previous.clone()).or_default().push(child);
+            } else {
+                open.push(Reverse((
+                    previous_cost + previous.heuristic(initial_state),
+                    child,
+                )));
+            }
+            nodes.push(RegressionNode {
+                condition: previous,
+                next: Some((index, action.clone())),
+                cost: previous_cost,
+                depth,
+            });
+            max_depth = max_depth.max(depth);
+        }
+    }
+
+    let stats = SearchStats {
+        expanded,
+        frontier: 0,
+        deepest_state: initial_state.clone(),
+        max_depth,
+    };
+    Err(if pruned {
+        PlanError::BudgetExhausted(stats)
+    } else {
+        PlanError::Exhausted(stats)
+    })
+}
+
+/// Returns whether the condition is already part of the chain ending at the node.
+fn on_chain<K: Eq, A, C>(nodes: &[RegressionNode<K, A, C>], index: usize, condition: &K) -> bool {
+    let mut current = Some(index);
+    while let Some(chained) = current {
+        if nodes[chained].condition == *condition {
+            return true;
+        }
+        current = nodes[chained].next.as_ref().map(|(next, _)| *next);
+    }
+    false
+}
+
+/// Returns the plan if its actions can be applied in order from the initial state, and satisfy the goal.
+fn validate<S, A, G, C>(initial_state: &S, actions: Vec<A>, goal: &G) -> Option<Plan<S, A, C>>
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    G: Goal<S, C>,
+    C: Cost,
+{
+    let mut state = initial_state.clone();
+    for action in &actions {
+        if !action.is_applicable(&state) {
+            return None;
+        }
+        action.apply_mut(&mut state);
+    }
+    if goal.is_satisfied(&state) {
+        Some(Plan::from_actions(initial_state, actions))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    struct Camp {
+        has_axe: bool,
+        has_wood: bool,
+        fire: bool,
+        position: u8,
+    }
+
+    #[derive(PartialEq, Eq, Hash, Clone, Debug, Default)]
+    struct Needs {
+        has_axe: Option<bool>,
+        has_wood: Option<bool>,
+        fire: Option<bool>,
+    }
+
+    impl Goal<Camp> for Needs {
+        fn is_satisfied(&self, state: &Camp) -> bool {
+            self.has_axe.is_none_or(|v| v == state.has_axe)
+                && self.has_wood.is_none_or(|v| v == state.has_wood)
+                && self.fire.is_none_or(|v| v == state.fire)
+        }
+    }
+
+    #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    enum CampAction {
+        GetAxe,
+        ChopWood,
+        /// Lights a fire, using up the wood.
+        LightFire,
+        Wander(u8),
+        /// Claims to light a fire without any wood, but doesn't.
+        Pretend,
+        /// Claims to take wood when not holding an axe, but doesn't.
+        Steal,
+    }
+
+    impl Action<Camp> for CampAction {
+        fn is_applicable(&self, state: &Camp) -> bool {
+            match self {
+                CampAction::GetAxe => !state.has_axe,
+                CampAction::ChopWood => state.has_axe,
+                CampAction::LightFire => state.has_wood,
+                CampAction::Wander(position) => state.position != *position,
+                CampAction::Pretend => true,
+                CampAction::Steal => !state.has_axe,
+            }
+        }
+
+        fn apply_mut(&self, state: &mut Camp) {
+            match self {
+                CampAction::GetAxe => state.has_axe = true,
+                CampAction::ChopWood => state.has_wood = true,
+                CampAction::LightFire => {
+                    state.has_wood = false;
+                    state.fire = true;
+                }
+                CampAction::Wander(position) => state.position = *position,
+                CampAction::Pretend | CampAction::Steal => {}
+            }
+        }
+
+        fn cost(&self, _state: &Camp) -> i32 {
+            match self {
+                CampAction::Pretend | CampAction::Steal => 0,
+                _ => 1,
+            }
+        }
+    }
+
+    impl RegressiveAction<Camp> for CampAction {
+        type Condition = Needs;
+
+        fn regress(&self, condition: &Needs) -> Option<Needs> {
+            match self {
+                CampAction::GetAxe if condition.has_axe == Some(true) => Some(Needs {
+                    has_axe: Some(false),
+                    ..condition.clone()
+                }),
+                CampAction::ChopWood
+                    if condition.has_wood == Some(true) && condition.has_axe != Some(false) =>
+                {
+                    Some(Needs {
+                        has_axe: Some(true),
+                        has_wood: None,
+                        ..condition.clone()
+                    })
+                }
+                CampAction::LightFire
+                    if condition.fire == Some(true) && condition.has_wood != Some(true) =>
+                {
+                    Some(Needs {
+                        has_wood: Some(true),
+                        fire: None,
+                        ..condition.clone()
+                    })
+                }
+                CampAction::Pretend if condition.fire == Some(true) => Some(Needs {
+                    fire: None,
+                    ..condition.clone()
+                }),
+                CampAction::Steal
+                    if condition.has_wood == Some(true) && condition.has_axe != Some(true) =>
+                {
+                    Some(Needs {
+                        has_axe: Some(false),
+                        has_wood: None,
+                        ..condition.clone()
+                    })
+                }
+                _ => None,
+            }
+        }
+
+        fn regression_cost(&self, _condition: &Needs) -> i32 {
+            match self {
+                CampAction::Pretend | CampAction::Steal => 0,
+                _ => 1,
+            }
+        }
+    }
+
+    fn camp() -> Camp {
+        Camp {
+            has_axe: false,
+            has_wood: false,
+            fire: false,
+            position: 0,
+        }
+    }
+
+    #[test]
+    fn backward_plan() {
+        use CampAction::*;
+        let mut actions: Vec<_> = (0..50).map(Wander).collect();
+        actions.extend([GetAxe, ChopWood, LightFire]);
+
+        let goal = Needs {
+            fire: Some(true),
+            ..Needs::default()
+        };
+        let plan = try_plan_backward(&camp(), &actions, &goal, &SearchBudget::unlimited()).unwrap();
+        assert_eq!(plan.cost(), 3);
+        assert_eq!(plan.into_actions(), vec![GetAxe, ChopWood, LightFire]);
+
+        // Lighting the fire uses up the wood, so more must be chopped afterwards
+        let goal = Needs {
+            has_wood: Some(true),
+            fire: Some(true),
+            ..Needs::default()
+        };
+        let (path, _) = plan_backward(&camp(), &actions, &goal).unwrap();
+        assert_eq!(path, vec![GetAxe, ChopWood, LightFire, ChopWood]);
+
+        // An axe can't be put down
+        let goal = Needs {
+            has_axe: Some(false),
+            has_wood: Some(true),
+            ..Needs::default()
+        };
+        let result = try_plan_backward(&camp(), &actions, &goal, &SearchBudget::unlimited());
+        assert!(matches!(result, Err(PlanError::Exhausted(_))));
+    }
+
+    #[test]
+    fn backward_validation() {
+        use CampAction::*;
+        let actions = [Pretend, GetAxe, ChopWood, LightFire];
+        let goal = Needs {
+            fire: Some(true),
+            ..Needs::default()
+        };
+
+        // Pretending is found first, as it is cheapest, but doesn't light the fire
+        let (path, cost) = plan_backward(&camp(), &actions, &goal).unwrap();
+        assert_eq!(path, vec![GetAxe, ChopWood, LightFire]);
+        assert_eq!(cost, 3);
+
+        let budget = SearchBudget::unlimited().with_max_depth(2);
+        let result = try_plan_backward(&camp(), &actions, &goal, &budget);
+        assert!(matches!(result, Err(PlanError::BudgetExhausted(_))));
+    }
+
+    #[test]
+    fn backward_validation_shared_condition() {
+        use CampAction::*;
+        let actions = [Steal, GetAxe, ChopWood];
+        let goal = Needs {
+            has_wood: Some(true),
+            ..Needs::default()
+        };
+
+        // Stealing reaches not having an axe first, and more expensive chains to it must still be searched
+        let (path, cost) = plan_backward(&camp(), &actions, &goal).unwrap();
+        assert_eq!(path, vec![GetAxe, ChopWood]);
+        assert_eq!(cost, 2);
+    }
+}
@@ -0,0 +1,345 @@
+use crate::{Cost, FactValue, Goal, StateKey, StripsAction, WorldState};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::Hash;
+use std::sync::Arc;
+
+/// A heuristic derived automatically from the structure of `StripsAction`s.
+///
+/// Apart from `GoalCount`, each heuristic solves a *relaxed* problem, where actions only ever add facts to the state.
+/// Since facts are never lost, the relaxed problem is much easier to solve than the real one.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum RelaxedHeuristic {
+    /// The number of goal facts which are not yet met.
+    ///
+    /// This is cheap to compute, but it overestimates when actions are cheaper than one or meet several facts at once.
+    GoalCount,
+    /// The cost of the most expensive goal fact, where each fact costs as much as reaching its most expensive
+    /// precondition and applying its cheapest achiever.
+    ///
+    /// This never overestimates, but it is often much lower than the real cost.
+    #[default]
+    HMax,
+    /// The sum of the costs of the goal facts, where each fact costs as much as reaching all of its preconditions
+    /// and applying its cheapest achiever.
+    ///
+    /// This is usually more informed than `HMax`, but it may overestimate when facts share preconditions.
+    HAdd,
+    /// The cost of a plan for the relaxed problem, extracted using the cheapest achievers found by `HAdd`.
+    ///
+    /// This counts each action once, so it overestimates less often than `HAdd` while still being well informed.
+    FastForward,
+}
+
+impl RelaxedHeuristic {
+    /// Estimates the cost to satisfy the goal from the given state.
+    ///
+    /// Returns `None` if the goal can't be satisfied even in the relaxed problem, which means that it can't be
+    /// satisfied at all.
+    pub fn estimate<K, C>(
+        &self,
+        state: &WorldState<K>,
+        goal: &WorldState<K>,
+        actions: &[StripsAction<K, C>],
+    ) -> Option<C>
+    where
+        K: StateKey,
+        C: Cost,
+    {
+        let unmet = state.unmet(goal).facts();
+        match self {
+            RelaxedHeuristic::GoalCount => {
+                Some(unmet.iter().fold(C::ZERO, |total, _| total + C::ONE))
+            }
+            RelaxedHeuristic::HMax => {
+                let costs = Relaxation::new(state, actions, true).costs_of(&unmet)?;
+                Some(costs.into_iter().max().unwrap_or(C::ZERO))
+            }
+            RelaxedHeuristic::HAdd => {
+                let costs = Relaxation::new(state, actions, false).costs_of(&unmet)?;
+                Some(costs.into_iter().fold(C::ZERO, |a, b| a + b))
+            }
+            RelaxedHeuristic::FastForward => {
+                let relaxed = Relaxation::new(state, actions, false);
+                relaxed.costs_of(&unmet)?;
+                Some(relaxed.plan_cost(actions, unmet))
+            }
+        }
+    }
+}
+
+/// The cheapest cost of reaching each fact in the relaxed problem, with the action which achieves it.
+struct Relaxation<K, C> {
+    costs: HashMap<(K, FactValue), C>,
+    achievers: HashMap<(K, FactValue), usize>,
+}
+
+impl<K: StateKey, C: Cost> Relaxation<K, C> {
+    fn new(state: &WorldState<K>, actions: &[StripsAction<K, C>], max: bool) -> Self {
+        let mut costs: HashMap<_, _> = state
+            .facts()
+            .into_iter()
+            .map(|fact| (fact, C::ZERO))
+            .collect();
+        let mut achievers = HashMap::new();
+
+        // Costs only ever decrease, so this reaches a fixpoint
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (index, action) in actions.iter().enumerate() {
+                let mut reached = C::ZERO;
+                let mut applicable = true;
+                for fact in action.preconditions().facts() {
+                    match costs.get(&fact) {
+                        Some(&cost) if max => reached = reached.max(cost),
+                        Some(&cost) => reached = reached + cost,
+                        None => {
+                            applicable = false;
+                            break;
+                        }
+                    }
+                }
+                if !applicable {
+                    continue;
+                }
+
+                let cost = reached + action.cost;
+                for fact in action.effects().facts() {
+                    if costs.get(&fact).is_none_or(|&previous| cost < previous) {
+                        costs.insert(fact, cost);
+                        achievers.insert(fact, index);
+                        changed = true;
+                    }
+                }
+            }
+        }
+        Self { costs, achievers }
+    }
+
+    /// Returns the cost of reaching each of the facts, or `None` if any of them can't be reached.
+    fn costs_of(&self, facts: &[(K, FactValue)]) -> Option<Vec<C>> {
+        facts
+            .iter()
+            .map(|fact| self.costs.get(fact).copied())
+            .collect()
+    }
+
+    /// Returns the total cost of the achievers needed to reach the facts, counting each action once.
+    fn plan_cost(&self, actions: &[StripsAction<K, C>], facts: Vec<(K, FactValue)>) -> C {
+        let mut used = HashSet::new();
+        let mut open = facts;
+        let mut total = C::ZERO;
+        while let Some(fact) = open.pop() {
+            // Facts without an achiever hold in the state
+            let Some(&index) = self.achievers.get(&fact) else {
+                continue;
+            };
+            if used.insert(index) {
+                total = total + actions[index].cost;
+                open.extend(actions[index].preconditions().facts());
+            }
+        }
+        total
+    }
+}
+
+/// A goal made of facts which must hold, with a heuristic derived from the actions used to plan for it.
+///
+/// Unlike using a `WorldState` as a goal directly, which gives an uninformed search,
+/// this estimates the cost to the goal with a `RelaxedHeuristic`, so fewer states are searched.
+///
+/// States from which the goal can't be reached, even in the relaxed problem, are estimated to cost more than
+/// all of the actions together.
+///
+/// The actions are stored in an `Arc`, so goals created from the same `Arc` share them rather than each
+/// holding a copy.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+///
+/// let actions: Vec<StripsAction> = vec![
+///     StripsAction::new("get axe").requires(0, false).adds(0),
+///     StripsAction::new("chop wood").requires(0, true).adds(1),
+///     StripsAction::new("light fire").requires(1, true).adds(2).with_cost(2),
+/// ];
+///
+/// let state = WorldState::new().with(0, false).with(1, false).with(2, false);
+/// let goal = FactGoal::new(WorldState::new().with(2, true), actions.clone())
+///     .with_heuristic(RelaxedHeuristic::HAdd);
+/// assert_eq!(goal.heuristic(&state), 4);
+///
+/// let (path, cost) = plan(&state, &actions, &goal).unwrap();
+/// assert_eq!(path, actions);
+/// assert_eq!(cost, 4);
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct FactGoal<K = usize, C = i32> {
+    name: String,
+    condition: WorldState<K>,
+    actions: Arc<[StripsAction<K, C>]>,
+    heuristic: RelaxedHeuristic,
+    priority: C,
+}
+
+impl<K: StateKey, C: Cost> FactGoal<K, C> {
+    /// Creates a goal which is satisfied when the condition holds, using the actions to derive its heuristic.
+    ///
+    /// The goal uses the `HMax` heuristic, which never overestimates, and has a priority of zero.
+    pub fn new(condition: WorldState<K>, actions: impl Into<Arc<[StripsAction<K, C>]>>) -> Self {
+        Self {
+            name: String::new(),
+            condition,
+            actions: actions.into(),
+            heuristic: RelaxedHeuristic::default(),
+            priority: C::ZERO,
+        }
+    }
+
+    /// Sets the name of the goal, which identifies it in an `Agent`.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Sets the heuristic used to estimate the cost to the goal.
+    pub fn with_heuristic(mut self, heuristic: RelaxedHeuristic) -> Self {
+        self.heuristic = heuristic;
+        self
+    }
+
+    /// Sets the constant priority of the goal.
+    pub fn with_priority(mut self, priority: C) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Returns the name of the goal, which is empty unless one was given.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the facts which must hold for the goal to be satisfied.
+    pub fn condition(&self) -> &WorldState<K> {
+        &self.condition
+    }
+
+    /// Returns the heuristic used to estimate the cost to the goal.
+    pub fn relaxed_heuristic(&self) -> RelaxedHeuristic {
+        self.heuristic
+    }
+}
+
+impl<K: StateKey, C: fmt::Debug> fmt::Debug for FactGoal<K, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FactGoal")
+            .field("name", &self.name)
+            .field("condition", &self.condition)
+            .field("heuristic", &self.heuristic)
+            .field("priority", &self.priority)
+            .finish()
+    }
+}
+
+impl<K: StateKey, C: Cost + Hash> Goal<WorldState<K>, C> for FactGoal<K, C> {
+    fn is_satisfied(&self, state: &WorldState<K>) -> bool {
+        state.matches(&self.condition)
+    }
+
+    fn heuristic(&self, state: &WorldState<K>) -> C {
+        self.heuristic
+            .estimate(state, &self.condition, &self.actions)
+            .unwrap_or_else(|| {
+                let total = self.actions.iter().fold(C::ZERO, |a, b| a + b.cost);
+                total + C::ONE
+            })
+    }
+
+    fn priority(&self, _state: &WorldState<K>) -> C {
+        self.priority
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{check_heuristic, try_plan, SearchBudget};
+
+    fn actions() -> Vec<StripsAction> {
+        vec![
+            StripsAction::new("get axe").requires(0, false).adds(0),
+            StripsAction::new("chop wood").requires(0, true).sets(1, 3),
+            StripsAction::new("gather stones").adds(2).with_cost(2),
+            StripsAction::new("build fire")
+                .requires(1, 3)
+                .requires(2, true)
+                .adds(3)
+                .with_cost(3),
+            StripsAction::new("cook")
+                .requires(3, true)
+                .requires(0, true)
+                .adds(4),
+        ]
+    }
+
+    fn state() -> WorldState {
+        WorldState::new().with(0, false).with(1, 0).with(2, false)
+    }
+
+    #[test]
+    fn relaxed_estimates() {
+        let actions = actions();
+        let goal = WorldState::new().with(4, true);
+        let estimate = |heuristic: RelaxedHeuristic| heuristic.estimate(&state(), &goal, &actions);
+
+        assert_eq!(estimate(RelaxedHeuristic::GoalCount), Some(1));
+        // Building the fire needs wood at 2 and stones at 2, then costs 3 more
+        assert_eq!(estimate(RelaxedHeuristic::HMax), Some(6));
+        // The axe is counted for both the wood and the cooking
+        assert_eq!(estimate(RelaxedHeuristic::HAdd), Some(9));
+        assert_eq!(estimate(RelaxedHeuristic::FastForward), Some(8));
+
+        let satisfied = state().with(4, true);
+        assert_eq!(
+            RelaxedHeuristic::FastForward.estimate(&satisfied, &goal, &actions),
+            Some(0)
+        );
+
+        // Without the axe, there is no way to cook
+        let no_axe = &actions[1..];
+        let from = WorldState::new().with(0, false);
+        assert_eq!(RelaxedHeuristic::HMax.estimate(&from, &goal, no_axe), None);
+        let goal = FactGoal::new(goal, no_axe.to_vec());
+        assert_eq!(goal.heuristic(&from), 8);
+        assert_eq!(goal.heuristic(&state().with(0, true)), 6);
+    }
+
+    #[test]
+    fn relaxed_planning() {
+        let actions = actions();
+        let condition = WorldState::new().with(4, true);
+
+        let blind = try_plan(&state(), &actions, &condition, &SearchBudget::unlimited()).unwrap();
+        assert_eq!(blind.cost(), 8);
+
+        for heuristic in [
+            RelaxedHeuristic::GoalCount,
+            RelaxedHeuristic::HMax,
+            RelaxedHeuristic::HAdd,
+            RelaxedHeuristic::FastForward,
+        ] {
+            let goal = FactGoal::new(condition.clone(), actions.clone()).with_heuristic(heuristic);
+            let plan = try_plan(&state(), &actions, &goal, &SearchBudget::unlimited()).unwrap();
+            assert_eq!(plan.cost(), 8);
+        }
+
+        // The admissible heuristic passes the check
+        let goal = FactGoal::new(condition, actions.clone());
+        let report = check_heuristic(&state(), &actions, &goal, 100);
+        assert!(report.complete);
+        assert!(report.is_admissible());
+        assert!(report.is_consistent());
+    }
+}
@@ -0,0 +1,235 @@
+use crate::Agent;
+use bevy::ecs::prelude::*;
+use bevy::ecs::system::{ReadOnlySystemParam, SystemParamItem};
+use std::hash::Hash;
+
+/// Reads data from the ECS into the state of each agent, registered with `PlanningPlugin::with_sensor`.
+///
+/// Sensors run every frame in the `SensorSystems` set, before planning. The sensed state is only written
+/// to an agent if it differs from its current state, so agents whose state didn't change aren't marked as changed.
+/// This means their plans aren't invalidated, and agents for which planning failed are only searched for again
+/// once what they sense changes.
+///
+/// The parameter of a sensor is any read-only system parameter, using the `'static` lifetime,
+/// such as the `SQuery` and `SRes` aliases from `bevy::ecs::system::lifetimeless`.
+/// It can't access the agents themselves, as sensors write to them.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+/// use bevy::app::App;
+/// use bevy::ecs::prelude::*;
+/// use bevy::ecs::system::lifetimeless::{SQuery, SRes};
+/// use bevy::ecs::system::SystemParamItem;
+///
+/// #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// struct State {
+///     position: i32,
+///     raining: bool,
+/// }
+///
+/// #[derive(Component)]
+/// struct Position(i32);
+///
+/// #[derive(Resource)]
+/// struct Weather {
+///     raining: bool,
+/// }
+///
+/// struct Senses;
+///
+/// impl Sensor<State> for Senses {
+///     type Param = (SQuery<&'static Position>, SRes<Weather>);
+///
+///     fn sense(
+///         &self,
+///         entity: Entity,
+///         state: &mut State,
+///         (positions, weather): &SystemParamItem<Self::Param>,
+///     ) {
+///         if let Ok(position) = positions.get(entity) {
+///             state.position = position.0;
+///         }
+///         state.raining = weather.raining;
+///     }
+/// }
+///
+/// # #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// # struct Wait;
+/// # impl Action<State> for Wait {
+/// #     fn is_applicable(&self, _state: &State) -> bool {
+/// #         false
+/// #     }
+/// #     fn apply_mut(&self, _state: &mut State) {}
+/// # }
+/// # #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// # struct Dry;
+/// # impl Goal<State> for Dry {
+/// #     fn is_satisfied(&self, state: &State) -> bool {
+/// #         !state.raining
+/// #     }
+/// # }
+/// type MyAgent = Agent<State, Wait, Dry>;
+///
+/// let mut app = App::new();
+/// app.add_plugins(PlanningPlugin::<State, Wait, Dry>::new().with_sensor(Senses))
+///     .insert_resource(Weather { raining: false });
+/// let state = State { position: 0, raining: false };
+/// let entity = app
+///     .world_mut()
+///     .spawn((MyAgent::new(state, vec![], vec![Dry]), Position(4)))
+///     .id();
+///
+/// app.update();
+/// let agent = app.world().get::<MyAgent>(entity).unwrap();
+/// assert_eq!(agent.state, State { position: 4, raining: false });
+/// ```
+pub trait Sensor<S>: Send + Sync + 'static {
+    /// The queries and resources read by the sensor.
+    type Param: ReadOnlySystemParam;
+
+    /// Writes what the agent of the entity senses into its state.
+    fn sense(&self, entity: Entity, state: &mut S, param: &SystemParamItem<Self::Param>);
+}
+
+/// The systems running the sensors registered with `PlanningPlugin`, which run in `Update` before `PlanningSystems`.
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SensorSystems;
+
+/// The agents written to by sensors.
+pub(crate) type SensedAgents<'w, 's, S, A, G> =
+    Query<'w, 's, (Entity, &'static mut Agent<S, A, G>)>;
+
+/// Runs a sensor for every agent, only writing the states which changed.
+pub(crate) fn run_sensor<S, A, G, T>(
+    sensor: &T,
+    param: &SystemParamItem<T::Param>,
+    agents: &mut SensedAgents<S, A, G>,
+) where
+    S: Clone + Hash + Eq + Send + Sync + 'static,
+    A: Send + Sync + 'static,
+    G: Send + Sync + 'static,
+    T: Sensor<S>,
+{
+    for (entity, mut agent) in agents {
+        let mut state = agent.state.clone();
+        sensor.sense(entity, &mut state, param);
+        if state != agent.state {
+            agent.state = state;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Action, CurrentPlan, Goal, PlanningPlugin, PlanningSystems};
+    use bevy::app::{App, Update};
+    use bevy::core::TaskPoolPlugin;
+    use bevy::ecs::system::lifetimeless::SQuery;
+
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    struct Forward;
+
+    impl Action<i32> for Forward {
+        fn is_applicable(&self, state: &i32) -> bool {
+            *state < 5
+        }
+
+        fn apply_mut(&self, state: &mut i32) {
+            *state += 1;
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    struct Reach(i32);
+
+    impl Goal<i32> for Reach {
+        fn is_satisfied(&self, state: &i32) -> bool {
+            *state == self.0
+        }
+    }
+
+    type TestAgent = Agent<i32, Forward, Reach>;
+
+    #[derive(Component)]
+    struct Position(i32);
+
+    struct SensePosition;
+
+    impl Sensor<i32> for SensePosition {
+        type Param = SQuery<&'static Position>;
+
+        fn sense(&self, entity: Entity, state: &mut i32, positions: &SystemParamItem<Self::Param>) {
+            if let Ok(position) = positions.get(entity) {
+                *state = position.0;
+            }
+        }
+    }
+
+    #[derive(Resource, Default)]
+    struct Changes(Vec<Entity>);
+
+    fn record_changes(agents: Query<Entity, Changed<TestAgent>>, mut changes: ResMut<Changes>) {
+        changes.0.extend(&agents);
+    }
+
+    fn plan_len(app: &App, entity: Entity) -> Option<usize> {
+        app.world()
+            .get::<CurrentPlan<i32, Forward, Reach>>(entity)
+            .map(|plan| plan.plan().len())
+    }
+
+    #[test]
+    fn sensor_change_detection() {
+        let mut app = App::new();
+        app.add_plugins((
+            TaskPoolPlugin::default(),
+            PlanningPlugin::<i32, Forward, Reach>::new()
+                .with_sensor(SensePosition)
+                .with_max_searches_per_frame(2),
+        ))
+        .init_resource::<Changes>()
+        .add_systems(
+            Update,
+            record_changes.after(SensorSystems).before(PlanningSystems),
+        );
+        let moved = app
+            .world_mut()
+            .spawn((
+                TestAgent::new(0, vec![Forward], vec![Reach(3)]),
+                Position(1),
+            ))
+            .id();
+        let still = app
+            .world_mut()
+            .spawn((
+                TestAgent::new(0, vec![Forward], vec![Reach(3)]),
+                Position(0),
+            ))
+            .id();
+
+        for _ in 0..5 {
+            app.update();
+        }
+        assert_eq!(app.world().get::<TestAgent>(moved).unwrap().state, 1);
+        assert_eq!(plan_len(&app, moved), Some(2));
+        assert_eq!(plan_len(&app, still), Some(3));
+
+        // Sensing the same state doesn't change the agents
+        app.world_mut().resource_mut::<Changes>().0.clear();
+        for _ in 0..5 {
+            app.update();
+        }
+        assert!(app.world().resource::<Changes>().0.is_empty());
+
+        // Only the agent whose sensed state changed is planned for again
+        app.world_mut().get_mut::<Position>(moved).unwrap().0 = 2;
+        for _ in 0..5 {
+            app.update();
+        }
+        assert_eq!(app.world().resource::<Changes>().0, vec![moved]);
+        assert_eq!(plan_len(&app, moved), Some(1));
+        assert_eq!(plan_len(&app, still), Some(3));
+    }
+}
@@ -0,0 +1,527 @@
+use crate::{
+    check_initial, Action, Cost, Goal, NodeOrder, Plan, PlanError, Search, SearchBudget,
+    SearchStats,
+};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+/// The algorithm used to search for a plan.
+///
+/// Strategies trade the optimality of plans for time and memory.
+/// A* is used by default, and is the only strategy used by `plan` and `Agent`.
+///
+/// ## Strategies
+/// - `AStar`: finds the cheapest plan if the goal's heuristic never overestimates.
+/// - `BreadthFirst`: finds the plan with the fewest actions, ignoring costs and the heuristic.
+/// - `UniformCost`: finds the cheapest plan, ignoring the heuristic (Dijkstra's algorithm).
+/// - `Greedy`: expands the state with the lowest heuristic first. This is often fast, but plans may be expensive.
+/// - `WeightedAStar`: multiplies the heuristic by a weight, which finds plans faster.
+///   With an admissible heuristic, plans cost at most `weight` times as much as the cheapest plan.
+///   Weights below 1, and NaN, are treated as 1.
+/// - `IdaStar`: iterative deepening A*, which finds the cheapest plan using memory proportional to its length,
+///   at the cost of searching states again in each iteration.
+/// - `Beam`: a breadth-first search which only keeps the `width` most promising states of each depth.
+///   Memory is bounded, but plans may be expensive or not found at all.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+///
+/// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+/// struct Counter(u32);
+///
+/// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+/// enum Add {
+///     One,
+///     Five,
+/// }
+///
+/// impl Action<Counter> for Add {
+///     fn is_applicable(&self, _state: &Counter) -> bool {
+///         true
+///     }
+///
+///     fn apply_mut(&self, state: &mut Counter) {
+///         state.0 += match self {
+///             Add::One => 1,
+///             Add::Five => 5,
+///         };
+///     }
+///
+///     fn cost(&self, _state: &Counter) -> i32 {
+///         match self {
+///             Add::One => 1,
+///             Add::Five => 10,
+///         }
+///     }
+/// }
+///
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// struct Reach(u32);
+///
+/// impl Goal<Counter> for Reach {
+///     fn is_satisfied(&self, state: &Counter) -> bool {
+///         state.0 == self.0
+///     }
+/// }
+///
+/// let actions = [Add::One, Add::Five];
+///
+/// let (path, cost) = plan_with_strategy(&Counter(0), &actions, &Reach(5), SearchStrategy::IdaStar).unwrap();
+/// assert_eq!(path, vec![Add::One; 5]);
+/// assert_eq!(cost, 5);
+///
+/// // Breadth-first search finds the shortest plan, not the cheapest
+/// let (path, cost) = plan_with_strategy(&Counter(0), &actions, &Reach(5), SearchStrategy::BreadthFirst).unwrap();
+/// assert_eq!(path, vec![Add::Five]);
+/// assert_eq!(cost, 10);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SearchStrategy {
+    #[default]
+    AStar,
+    BreadthFirst,
+    UniformCost,
+    Greedy,
+    WeightedAStar {
+        weight: f64,
+    },
+    IdaStar,
+    Beam {
+        width: usize,
+    },
+}
+
+/// Returns the plan and total cost to satisfy the goal, found using the given strategy.
+///
+/// This is equivalent to `plan` for `SearchStrategy::AStar`.
+pub fn plan_with_strategy<S, A, G, C>(
+    initial_state: &S,
+    actions: &[A],
+    goal: &G,
+    strategy: SearchStrategy,
+) -> Option<(Vec<A>, C)>
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    G: Goal<S, C> + Clone,
+    C: Cost,
+{
+    let budget = SearchBudget::unlimited();
+    match try_plan_with_strategy(initial_state, actions, goal, &budget, strategy) {
+        Ok(plan) => {
+            let cost = plan.cost();
+            Some((plan.into_actions(), cost))
+        }
+        Err(PlanError::AlreadySatisfied) => Some((vec![], C::ZERO)),
+        Err(_) => None,
+    }
+}
+
+/// Returns the plan to satisfy the goal found using the given strategy within the budget,
+/// or the reason none was found.
+///
+/// This is equivalent to `try_plan` for `SearchStrategy::AStar`.
+///
+/// As beam search discards states, it reports `PlanError::BudgetExhausted` if it fails after discarding any.
+pub fn try_plan_with_strategy<S, A, G, C>(
+    initial_state: &S,
+    actions: &[A],
+    goal: &G,
+    budget: &SearchBudget<C>,
+    strategy: SearchStrategy,
+) -> Result<Plan<S, A, C>, PlanError<S>>
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    G: Goal<S, C>,
+    C: Cost,
+{
+    if let Some(error) = check_initial(initial_state, actions, goal) {
+        return Err(error);
+    }
+
+    let order = match strategy {
+        SearchStrategy::IdaStar => return ida_star(initial_state, actions, goal, budget),
+        SearchStrategy::Beam { width } => return beam(initial_state, actions, goal, budget, width),
+        SearchStrategy::AStar => NodeOrder::AStar,
+        SearchStrategy::BreadthFirst => NodeOrder::BreadthFirst,
+        SearchStrategy::UniformCost => NodeOrder::UniformCost,
+        SearchStrategy::Greedy => NodeOrder::Greedy,
+        SearchStrategy::WeightedAStar { weight } => NodeOrder::WeightedAStar(weight.max(1.0)),
+    };
+    let mut search = Search::with_order(initial_state, goal, order);
+    let outcome = search.run(actions, goal, budget);
+    search.result(outcome)
+}
+
+/// A state on the current path of an IDA* search, with the actions left to try from it.
+struct Frame<S, A, C> {
+    state: S,
+    cost: C,
+    children: std::vec::IntoIter<(A, C)>,
+}
+
+/// Searches depth-first for a plan within a cost threshold, raising the threshold until a plan is found.
+fn ida_star<S, A, G, C>(
+    initial_state: &S,
+    actions: &[A],
+    goal: &G,
+    budget: &SearchBudget<C>,
+) -> Result<Plan<S, A, C>, PlanError<S>>
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    G: Goal<S, C>,
+    C: Cost,
+{
+    let children = |state: &S| -> std::vec::IntoIter<(A, C)> {
+        actions
+            .iter()
+            .filter(|action| action.is_applicable(state))
+            .map(|action| (action.clone(), action.cost(state)))
+            .collect::<Vec<_>>()
+            .into_iter()
+    };
+
+    let mut threshold = goal.heuristic(initial_state);
+    let mut expanded = 0;
+    let mut pruned = false;
+    let mut deepest = (initial_state.clone(), 0);
+    loop {
+        let mut next_threshold: Option<C> = None;
+        let mut stack = vec![Frame {
+            state: initial_state.clone(),
+            cost: C::ZERO,
+            children: children(initial_state),
+        }];
+        let mut path: Vec<A> = Vec::new();
+        if budget.is_spent(expanded) {
+            return Err(PlanError::BudgetExhausted(SearchStats {
+                expanded,
+                frontier: 1,
+                deepest_state: deepest.0,
+                max_depth: deepest.1,
+            }));
+        }
+        expanded += 1;
+
+        while let Some(frame) = stack.last_mut() {
+            let Some((action, step_cost)) = frame.children.next() else {
+                stack.pop();
+                path.pop();
+                continue;
+            };
+            let state = action.apply(&frame.state);
+            let cost = frame.cost + step_cost;
+            let depth = stack.len();
+            if !budget.allows(depth, cost) {
+                pruned = true;
+                continue;
+            }
+            if stack.iter().any(|frame| frame.state == state) {
+                continue; // Cycle on the current path
+            }
+
+            let estimate = cost + goal.heuristic(&state);
+            if estimate > threshold {
+                next_threshold = Some(next_threshold.map_or(estimate, |next| next.min(estimate)));
+                continue;
+            }
+            path.push(action);
+            if goal.is_satisfied(&state) {
+                return Ok(Plan::from_actions(initial_state, path));
+            }
+            if depth > deepest.1 {
+                deepest = (state.clone(), depth);
+            }
+            if budget.is_spent(expanded) {
+                return Err(PlanError::BudgetExhausted(SearchStats {
+                    expanded,
+                    frontier: stack.len(),
+                    deepest_state: deepest.0,
+                    max_depth: deepest.1,
+                }));
+            }
+            expanded += 1;
+            stack.push(Frame {
+                children: children(&state),
+                state,
+                cost,
+            });
+        }
+
+        let stats = SearchStats {
+            expanded,
+            frontier: 0,
+            deepest_state: deepest.0.clone(),
+            max_depth: deepest.1,
+        };
+        match next_threshold {
+            Some(next) => threshold = next,
+            None if pruned => return Err(PlanError::BudgetExhausted(stats)),
+            None => return Err(PlanError::Exhausted(stats)),
+        }
+    }
+}
+
+/// A state reached by beam search, linked to the state it was reached from.
+struct BeamNode<S, A, C> {
+    state: S,
+    parent: Option<(usize, A)>,
+    cost: C,
+}
+
+/// Searches breadth-first, keeping only the given number of states with the lowest estimates at each depth.
+fn beam<S, A, G, C>(
+    initial_state: &S,
+    actions: &[A],
+    goal: &G,
+    budget: &SearchBudget<C>,
+    width: usize,
+) -> Result<Plan<S, A, C>, PlanError<S>>
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    G: Goal<S, C>,
+    C: Cost,
+{
+    let mut nodes: Vec<BeamNode<S, A, C>> = vec![BeamNode {
+        state: initial_state.clone(),
+        parent: None,
+        cost: C::ZERO,
+    }];
+    let mut seen = HashSet::from([initial_state.clone()]);
+    let mut layer = vec![0];
+    let mut depth = 0;
+    let mut deepest = (0, 0);
+    let mut expanded = 0;
+    let mut pruned = false;
+    loop {
+        if let Some(&index) = layer
+            .iter()
+            .find(|&&index| goal.is_satisfied(&nodes[index].state))
+        {
+            let mut path = Vec::new();
+            let mut current = index;
+            while let Some((parent, action)) = &nodes[current].parent {
+                path.push(action.clone());
+                current = *parent;
+            }
+            path.reverse();
+            return Ok(Plan::from_actions(initial_state, path));
+        }
+
+        if let Some(&index) = layer.first() {
+            deepest = (index, depth);
+        }
+        let stats = |layer: &[usize], expanded| SearchStats {
+            expanded,
+            frontier: layer.len(),
+            deepest_state: nodes[deepest.0].state.clone(),
+            max_depth: deepest.1,
+        };
+        if layer.is_empty() {
+            return Err(if pruned {
+                PlanError::BudgetExhausted(stats(&layer, expanded))
+            } else {
+                PlanError::Exhausted(stats(&layer, expanded))
+            });
+        }
+
+        // Keep the cheapest way to reach each new state, in the order they were reached
+        depth += 1;
+        let mut candidates: Vec<BeamNode<S, A, C>> = Vec::new();
+        let mut candidate_index: HashMap<S, usize> = HashMap::new();
+        for &index in &layer {
+            if budget.is_spent(expanded) {
+                return Err(PlanError::BudgetExhausted(stats(&layer, expanded)));
+            }
+            expanded += 1;
+            let node = &nodes[index];
+            for action in actions.iter().filter(|a| a.is_applicable(&node.state)) {
+                let state = action.apply(&node.state);
+                let cost = node.cost + action.cost(&node.state);
+                if seen.contains(&state) {
+                    continue;
+                }
+                if !budget.allows(depth, cost) {
+                    pruned = true;
+                    continue;
+                }
+                let candidate = BeamNode {
+                    state: state.clone(),
+                    parent: Some((index, action.clone())),
+                    cost,
+                };
+                match candidate_index.get(&state) {
+                    Some(&i) if cost < candidates[i].cost => candidates[i] = candidate,
+                    Some(_) => {}
+                    None => {
+                        candidate_index.insert(state, candidates.len());
+                        candidates.push(candidate);
+                    }
+                }
+            }
+        }
+
+        let mut candidates: Vec<(C, BeamNode<S, A, C>)> = candidates
+            .into_iter()
+            .map(|node| (node.cost + goal.heuristic(&node.state), node))
+            .collect();
+        candidates.sort_by_key(|(estimate, node)| (*estimate, node.cost));
+        if candidates.len() > width {
+            candidates.truncate(width);
+            pruned = true;
+        }
+
+        layer.clear();
+        for (_, node) in candidates {
+            seen.insert(node.state.clone());
+            layer.push(nodes.len());
+            nodes.push(node);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    struct Position(i32, i32);
+
+    #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    enum Move {
+        Right,
+        Up,
+        /// Moves diagonally, which is shorter but more expensive than moving right then up.
+        Diagonal,
+    }
+
+    impl Action<Position> for Move {
+        fn is_applicable(&self, state: &Position) -> bool {
+            state.0 < 10 && state.1 < 10
+        }
+
+        fn apply_mut(&self, state: &mut Position) {
+            match self {
+                Move::Right => state.0 += 1,
+                Move::Up => state.1 += 1,
+                Move::Diagonal => {
+                    state.0 += 1;
+                    state.1 += 1;
+                }
+            }
+        }
+
+        fn cost(&self, _state: &Position) -> i32 {
+            match self {
+                Move::Right | Move::Up => 1,
+                Move::Diagonal => 3,
+            }
+        }
+    }
+
+    #[derive(PartialEq, Eq, Hash, Clone)]
+    struct Reach(i32, i32);
+
+    impl Goal<Position> for Reach {
+        fn is_satisfied(&self, state: &Position) -> bool {
+            state.0 == self.0 && state.1 == self.1
+        }
+
+        fn heuristic(&self, state: &Position) -> i32 {
+            (self.0 - state.0).max(0) + (self.1 - state.1).max(0)
+        }
+    }
+
+    fn search(strategy: SearchStrategy) -> Result<Plan<Position, Move>, PlanError<Position>> {
+        let actions = [Move::Right, Move::Up, Move::Diagonal];
+        let budget = SearchBudget::unlimited();
+        try_plan_with_strategy(&Position(0, 0), &actions, &Reach(3, 3), &budget, strategy)
+    }
+
+    #[test]
+    fn strategy_optimal() {
+        for strategy in [
+            SearchStrategy::AStar,
+            SearchStrategy::UniformCost,
+            SearchStrategy::IdaStar,
+            SearchStrategy::WeightedAStar { weight: 1.0 },
+        ] {
+            let plan = search(strategy).unwrap();
+            assert_eq!(plan.cost(), 6, "{strategy:?}");
+            assert_eq!(plan.final_state(), Some(&Position(3, 3)));
+        }
+
+        let plan = search(SearchStrategy::BreadthFirst).unwrap();
+        assert_eq!(plan.into_actions(), vec![Move::Diagonal; 3]);
+    }
+
+    #[test]
+    fn strategy_suboptimal() {
+        for strategy in [
+            SearchStrategy::Greedy,
+            SearchStrategy::WeightedAStar { weight: 2.0 },
+            SearchStrategy::Beam { width: 2 },
+        ] {
+            let plan = search(strategy).unwrap();
+            assert_eq!(plan.final_state(), Some(&Position(3, 3)), "{strategy:?}");
+            for (step, next) in plan.iter().zip(plan.iter().skip(1)) {
+                assert_eq!(step.post_state, next.pre_state);
+            }
+        }
+
+        // Weighted plans cost at most the weight times the optimal cost
+        let plan = search(SearchStrategy::WeightedAStar { weight: 1.5 }).unwrap();
+        assert!(plan.cost() <= 9);
+    }
+
+    #[test]
+    fn strategy_budget() {
+        let actions = [Move::Right, Move::Up, Move::Diagonal];
+        let budget = SearchBudget::unlimited().with_max_expanded(3);
+        for strategy in [SearchStrategy::IdaStar, SearchStrategy::Beam { width: 10 }] {
+            let result =
+                try_plan_with_strategy(&Position(0, 0), &actions, &Reach(3, 3), &budget, strategy);
+            let Err(PlanError::BudgetExhausted(stats)) = result else {
+                panic!("expected {strategy:?} to exhaust its budget");
+            };
+            assert_eq!(stats.expanded, 3);
+        }
+
+        // Unreachable goals exhaust the search, unless beam search discarded states
+        let budget = SearchBudget::unlimited();
+        for strategy in [SearchStrategy::IdaStar, SearchStrategy::Beam { width: 1 }] {
+            let result = try_plan_with_strategy(
+                &Position(0, 0),
+                &[Move::Right],
+                &Reach(20, 0),
+                &budget,
+                strategy,
+            );
+            let Err(PlanError::Exhausted(stats)) = result else {
+                panic!("expected {strategy:?} to exhaust the state space");
+            };
+            assert_eq!(stats.deepest_state, Position(10, 0));
+            assert_eq!(stats.max_depth, 10);
+        }
+        let result = try_plan_with_strategy(
+            &Position(0, 0),
+            &[Move::Right, Move::Up],
+            &Reach(20, 20),
+            &budget,
+            SearchStrategy::Beam { width: 1 },
+        );
+        assert!(matches!(result, Err(PlanError::BudgetExhausted(_))));
+    }
+
+    #[test]
+    fn strategy_clamps_weight() {
+        for weight in [0.5, -1.0, f64::NAN] {
+            let plan = search(SearchStrategy::WeightedAStar { weight }).unwrap();
+            assert_eq!(plan.cost(), 6, "{weight}");
+        }
+    }
+}
@@ -0,0 +1,313 @@
+use crate::{Action, Cost, FactValue, RegressiveAction, StateKey, WorldState};
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::Hash;
+
+/// An action over a `WorldState`, described by its preconditions and effects rather than code.
+///
+/// The action is applicable to any state which matches its preconditions,
+/// and applying it sets every fact in its effects. Boolean facts set to true are *added* by the action,
+/// and those set to false are *deleted*.
+///
+/// Since its structure is known, the planner can tell which actions are relevant to a goal.
+/// This allows irrelevant actions to be pruned with `relevant_actions`, and plans to be searched for backwards
+/// with `plan_backward`, as it implements `RegressiveAction` with world states as conditions.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+///
+/// const HAS_AXE: usize = 0;
+/// const HAS_WOOD: usize = 1;
+/// const FIRE: usize = 2;
+///
+/// let actions = vec![
+///     StripsAction::new("get axe").requires(HAS_AXE, false).adds(HAS_AXE),
+///     StripsAction::new("chop wood").requires(HAS_AXE, true).adds(HAS_WOOD),
+///     StripsAction::new("light fire")
+///         .requires(HAS_WOOD, true)
+///         .adds(FIRE)
+///         .deletes(HAS_WOOD)
+///         .with_cost(2),
+/// ];
+///
+/// let state = WorldState::new().with(HAS_AXE, false).with(HAS_WOOD, false).with(FIRE, false);
+/// let goal = WorldState::new().with(FIRE, true);
+///
+/// let (path, cost) = plan(&state, &actions, &goal).unwrap();
+/// let names: Vec<_> = path.iter().map(|action| action.name()).collect();
+/// assert_eq!(names, vec!["get axe", "chop wood", "light fire"]);
+/// assert_eq!(cost, 4);
+///
+/// // Searching backwards finds the same plan
+/// assert_eq!(plan_backward(&state, &actions, &goal), Some((path, cost)));
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "K: StateKey + serde::Serialize, C: serde::Serialize",
+        deserialize = "K: StateKey + serde::Deserialize<'de>, C: serde::Deserialize<'de>"
+    ))
+)]
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct StripsAction<K = usize, C = i32> {
+    name: String,
+    preconditions: WorldState<K>,
+    effects: WorldState<K>,
+    pub(crate) cost: C,
+}
+
+impl<K: StateKey, C: Cost> StripsAction<K, C> {
+    /// Creates an action with the given name, without any preconditions or effects.
+    ///
+    /// The name identifies the action in plans, and its cost is one.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            preconditions: WorldState::new(),
+            effects: WorldState::new(),
+            cost: C::ONE,
+        }
+    }
+
+    /// Requires a fact to have the given value for the action to be applicable.
+    pub fn requires(mut self, key: K, value: impl Into<FactValue>) -> Self {
+        self.preconditions.set(key, value);
+        self
+    }
+
+    /// Sets a fact to the given value when the action is applied.
+    pub fn sets(mut self, key: K, value: impl Into<FactValue>) -> Self {
+        self.effects.set(key, value);
+        self
+    }
+
+    /// Sets a boolean fact to true when the action is applied.
+    pub fn adds(self, key: K) -> Self {
+        self.sets(key, true)
+    }
+
+    /// Sets a boolean fact to false when the action is applied.
+    pub fn deletes(self, key: K) -> Self {
+        self.sets(key, false)
+    }
+
+    /// Sets the cost of the action.
+    pub fn with_cost(mut self, cost: C) -> Self {
+        self.cost = cost;
+        self
+    }
+
+    /// Returns the name of the action.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the facts which must hold for the action to be applicable.
+    pub fn preconditions(&self) -> &WorldState<K> {
+        &self.preconditions
+    }
+
+    /// Returns the facts which are set when the action is applied.
+    pub fn effects(&self) -> &WorldState<K> {
+        &self.effects
+    }
+
+    /// Returns true if the action sets any fact of the condition to its required value, without conflicting with it.
+    pub fn is_relevant(&self, condition: &WorldState<K>) -> bool {
+        !self.effects.conflicts_with(condition)
+            && self
+                .effects
+                .facts()
+                .into_iter()
+                .any(|(key, value)| condition.get(key) == Some(value))
+    }
+}
+
+impl<K: StateKey, C: fmt::Debug> fmt::Debug for StripsAction<K, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StripsAction")
+            .field("name", &self.name)
+            .field("preconditions", &self.preconditions)
+            .field("effects", &self.effects)
+            .field("cost", &self.cost)
+            .finish()
+    }
+}
+
+impl<K: StateKey, C: Cost + Hash> Action<WorldState<K>, C> for StripsAction<K, C> {
+    fn is_applicable(&self, state: &WorldState<K>) -> bool {
+        state.matches(&self.preconditions)
+    }
+
+    fn apply_mut(&self, state: &mut WorldState<K>) {
+        state.merge(&self.effects);
+    }
+
+    fn cost(&self, _state: &WorldState<K>) -> C {
+        self.cost
+    }
+}
+
+impl<K: StateKey, C: Cost + Hash> RegressiveAction<WorldState<K>, C> for StripsAction<K, C> {
+    type Condition = WorldState<K>;
+
+    fn regress(&self, condition: &WorldState<K>) -> Option<WorldState<K>> {
+        if !self.is_relevant(condition) {
+            return None;
+        }
+        // The effects satisfy their part of the condition, and the preconditions must hold for the rest
+        let mut previous = condition.clone();
+        for (key, _) in self.effects.facts() {
+            previous.unset(key);
+        }
+        if previous.conflicts_with(&self.preconditions) {
+            return None;
+        }
+        previous.merge(&self.preconditions);
+        Some(previous)
+    }
+
+    fn regression_cost(&self, _condition: &WorldState<K>) -> C {
+        self.cost
+    }
+}
+
+/// Returns the actions which may help to satisfy the goal, in their original order.
+///
+/// An action is relevant if it sets a fact required by the goal, or by the preconditions of another relevant action.
+/// Planning with only the relevant actions finds the same plans, but searches fewer states.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+///
+/// let actions: Vec<StripsAction> = vec![
+///     StripsAction::new("get axe").adds(0),
+///     StripsAction::new("chop wood").requires(0, true).adds(1),
+///     StripsAction::new("sing").adds(2),
+/// ];
+///
+/// let goal = WorldState::new().with(1, true);
+/// let relevant = relevant_actions(&actions, &goal);
+/// assert_eq!(relevant, actions[..2].to_vec());
+/// ```
+pub fn relevant_actions<K, C>(
+    actions: &[StripsAction<K, C>],
+    goal: &WorldState<K>,
+) -> Vec<StripsAction<K, C>>
+where
+    K: StateKey,
+    C: Cost,
+{
+    let mut required = goal.facts();
+    let mut relevant = HashSet::new();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (index, action) in actions.iter().enumerate() {
+            if relevant.contains(&index) {
+                continue;
+            }
+            let achieves = action
+                .effects
+                .facts()
+                .into_iter()
+                .any(|fact| required.contains(&fact));
+            if achieves {
+                relevant.insert(index);
+                required.extend(action.preconditions.facts());
+                changed = true;
+            }
+        }
+    }
+    actions
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| relevant.contains(index))
+        .map(|(_, action)| action.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{plan, plan_backward, try_plan, PlanError, SearchBudget};
+
+    fn actions() -> Vec<StripsAction> {
+        vec![
+            StripsAction::new("get axe").requires(0, false).adds(0),
+            StripsAction::new("drop axe").requires(0, true).deletes(0),
+            StripsAction::new("chop wood").requires(0, true).sets(1, 3),
+            StripsAction::new("burn wood")
+                .requires(1, 3)
+                .sets(1, 0)
+                .adds(2)
+                .with_cost(3),
+        ]
+    }
+
+    #[test]
+    fn strips_apply() {
+        let actions = actions();
+        let state = WorldState::new().with(0, false).with(1, 0).with(2, false);
+        assert!(actions[0].is_applicable(&state));
+        assert!(!actions[2].is_applicable(&state));
+
+        let state = actions[0].apply(&state);
+        assert_eq!(
+            state,
+            WorldState::new().with(0, true).with(1, 0).with(2, false)
+        );
+        assert_eq!(actions[3].cost(&state), 3);
+
+        let goal = WorldState::new().with(2, true);
+        let (path, cost) = plan(&state, &actions, &goal).unwrap();
+        assert_eq!(path, vec![actions[2].clone(), actions[3].clone()]);
+        assert_eq!(cost, 4);
+    }
+
+    #[test]
+    fn strips_regress() {
+        let actions = actions();
+        let goal = WorldState::new().with(2, true).with(0, false);
+        assert_eq!(
+            actions[3].regress(&goal),
+            Some(WorldState::new().with(0, false).with(1, 3))
+        );
+        // Chopping wood needs the axe, which the condition requires not to have
+        assert_eq!(
+            actions[2].regress(&WorldState::new().with(0, false).with(1, 3)),
+            None
+        );
+        assert!(!actions[0].is_relevant(&goal));
+        assert!(actions[1].is_relevant(&goal));
+
+        let state = WorldState::new().with(0, false).with(1, 0).with(2, false);
+        let (path, cost) = plan_backward(&state, &actions, &goal).unwrap();
+        let names: Vec<_> = path.iter().map(|action| action.name()).collect();
+        assert_eq!(names, vec!["get axe", "chop wood", "burn wood", "drop axe"]);
+        assert_eq!(cost, 6);
+        assert_eq!(plan(&state, &actions, &goal).unwrap().1, cost);
+    }
+
+    #[test]
+    fn strips_relevance() {
+        let mut actions = actions();
+        actions.extend((3..20).map(|key| StripsAction::new(format!("noise {key}")).adds(key)));
+        let goal = WorldState::new().with(2, true);
+
+        let relevant = relevant_actions(&actions, &goal);
+        let names: Vec<_> = relevant.iter().map(|action| action.name()).collect();
+        assert_eq!(names, vec!["get axe", "drop axe", "chop wood", "burn wood"]);
+
+        // Pruning irrelevant actions searches far fewer states
+        let state = WorldState::new().with(0, false).with(1, 0).with(2, false);
+        let budget = SearchBudget::unlimited().with_max_expanded(100);
+        let result = try_plan(&state, &actions, &goal, &budget);
+        assert!(matches!(result, Err(PlanError::BudgetExhausted(_))));
+        let plan = try_plan(&state, &relevant, &goal, &budget).unwrap();
+        assert_eq!(plan.cost(), 5);
+    }
+}
@@ -0,0 +1,389 @@
+use crate::{
+    check_initial, Action, Cost, FactValue, Goal, Plan, PlanError, Search, SearchBudget, StateKey,
+    StripsAction, WorldState,
+};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+/// Produces the actions which are applicable to a state, for each state expanded while searching.
+///
+/// A slice of actions is a generator which tests every action against each state.
+/// With many actions, a generator which only tests the actions that could be applicable,
+/// such as a `SuccessorTree`, can be much faster.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+///
+/// #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// struct Add(u32);
+///
+/// impl Action<u32> for Add {
+///     fn is_applicable(&self, state: &u32) -> bool {
+///         *state + self.0 <= 100
+///     }
+///
+///     fn apply_mut(&self, state: &mut u32) {
+///         *state += self.0;
+///     }
+/// }
+///
+/// /// Only generates additions which keep the state even.
+/// struct EvenAdditions(Vec<Add>);
+///
+/// impl SuccessorGenerator<u32, Add> for EvenAdditions {
+///     fn applicable(&self, state: &u32, out: &mut Vec<Add>) {
+///         let parity = state % 2;
+///         out.extend(
+///             self.0
+///                 .iter()
+///                 .filter(|action| action.0 % 2 == parity && action.is_applicable(state))
+///                 .cloned(),
+///         );
+///     }
+/// }
+///
+/// let generator = EvenAdditions((1..=10).map(Add).collect());
+/// let (path, cost) = plan_with_successors(&0, &generator, &Reach(24)).unwrap();
+/// assert_eq!(path.iter().map(|action| action.0).sum::<u32>(), 24);
+/// assert_eq!(cost, 3);
+///
+/// // Odd numbers can't be reached with even additions
+/// assert!(plan_with_successors(&0, &generator, &Reach(25)).is_none());
+/// # struct Reach(u32);
+/// # impl Goal<u32> for Reach {
+/// #     fn is_satisfied(&self, state: &u32) -> bool {
+/// #         *state == self.0
+/// #     }
+/// # }
+/// ```
+pub trait SuccessorGenerator<S, A, C = i32>
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    C: Cost,
+{
+    /// Adds the actions applicable to the state to `out`.
+    ///
+    /// Every action added must be applicable to the state.
+    fn applicable(&self, state: &S, out: &mut Vec<A>);
+}
+
+impl<S, A, C> SuccessorGenerator<S, A, C> for [A]
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    C: Cost,
+{
+    fn applicable(&self, state: &S, out: &mut Vec<A>) {
+        out.extend(
+            self.iter()
+                .filter(|action| action.is_applicable(state))
+                .cloned(),
+        );
+    }
+}
+
+/// A decision tree over the preconditions of `StripsAction`s, which finds the applicable actions
+/// without testing those whose preconditions are ruled out by the state.
+///
+/// Each node of the tree tests the value of one fact. Only the branch for the value in the state is followed,
+/// along with the branch for actions which don't require the fact,
+/// so each state only visits the actions which are applicable to it.
+/// Applicable actions are generated in the order they were given,
+/// so plans are the same as those found with a slice of the actions.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+///
+/// const HAS_AXE: usize = 0;
+/// const HAS_WOOD: usize = 1;
+/// const FIRE: usize = 2;
+///
+/// let actions = vec![
+///     StripsAction::new("get axe").requires(HAS_AXE, false).adds(HAS_AXE),
+///     StripsAction::new("chop wood").requires(HAS_AXE, true).adds(HAS_WOOD),
+///     StripsAction::new("light fire")
+///         .requires(HAS_WOOD, true)
+///         .adds(FIRE)
+///         .deletes(HAS_WOOD)
+///         .with_cost(2),
+/// ];
+/// let tree = SuccessorTree::new(actions.clone());
+///
+/// let state = WorldState::new().with(HAS_AXE, false).with(HAS_WOOD, false).with(FIRE, false);
+/// let mut applicable = Vec::new();
+/// tree.applicable(&state, &mut applicable);
+/// assert_eq!(applicable, vec![actions[0].clone()]);
+///
+/// let goal = WorldState::new().with(FIRE, true);
+/// assert_eq!(plan_with_successors(&state, &tree, &goal), plan(&state, &actions, &goal));
+/// ```
+pub struct SuccessorTree<K = usize, C = i32> {
+    actions: Vec<StripsAction<K, C>>,
+    root: TreeNode<K>,
+}
+
+/// A node of a `SuccessorTree`.
+struct TreeNode<K> {
+    /// The actions whose preconditions have all been tested on the way to this node.
+    actions: Vec<usize>,
+    /// The fact tested by this node, if any actions require further facts.
+    switch: Option<Switch<K>>,
+}
+
+/// Branches on the value of a fact.
+struct Switch<K> {
+    key: K,
+    /// The actions which require each value of the fact.
+    branches: HashMap<FactValue, TreeNode<K>>,
+    /// The actions which don't require the fact.
+    otherwise: Box<TreeNode<K>>,
+}
+
+impl<K: StateKey, C: Cost + Hash> SuccessorTree<K, C> {
+    /// Builds a tree over the preconditions of the actions.
+    pub fn new(actions: Vec<StripsAction<K, C>>) -> Self {
+        let preconditions: Vec<_> = actions
+            .iter()
+            .map(|action| action.preconditions().facts())
+            .collect();
+        let entries = preconditions
+            .iter()
+            .enumerate()
+            .map(|(index, facts)| (index, facts.as_slice()))
+            .collect();
+        Self {
+            root: TreeNode::new(entries),
+            actions,
+        }
+    }
+
+    /// Returns the actions in the tree, in the order they were given.
+    pub fn actions(&self) -> &[StripsAction<K, C>] {
+        &self.actions
+    }
+}
+
+impl<K: StateKey> TreeNode<K> {
+    /// Builds a node for actions with the given indices and untested preconditions, sorted by key index.
+    fn new(entries: Vec<(usize, &[(K, FactValue)])>) -> Self {
+        let (done, remaining): (Vec<_>, Vec<_>) = entries
+            .into_iter()
+            .partition(|(_, preconditions)| preconditions.is_empty());
+        let actions = done.into_iter().map(|(index, _)| index).collect();
+
+        // Testing the lowest key first keeps the keys along each path increasing
+        let Some(key) = remaining
+            .iter()
+            .map(|(_, preconditions)| preconditions[0].0)
+            .min_by_key(|key| key.index())
+        else {
+            return Self {
+                actions,
+                switch: None,
+            };
+        };
+
+        let mut branches: HashMap<FactValue, Vec<_>> = HashMap::new();
+        let mut otherwise = Vec::new();
+        for (index, preconditions) in remaining {
+            let (fact, value) = preconditions[0];
+            if fact == key {
+                branches
+                    .entry(value)
+                    .or_default()
+                    .push((index, &preconditions[1..]));
+            } else {
+                otherwise.push((index, preconditions));
+            }
+        }
+
+        Self {
+            actions,
+            switch: Some(Switch {
+                key,
+                branches: branches
+                    .into_iter()
+                    .map(|(value, entries)| (value, TreeNode::new(entries)))
+                    .collect(),
+                otherwise: Box::new(TreeNode::new(otherwise)),
+            }),
+        }
+    }
+
+    /// Adds the indices of the actions applicable to the state.
+    fn collect(&self, state: &WorldState<K>, out: &mut Vec<usize>) {
+        out.extend(&self.actions);
+        if let Some(switch) = &self.switch {
+            if let Some(branch) = state
+                .get(switch.key)
+                .and_then(|value| switch.branches.get(&value))
+            {
+                branch.collect(state, out);
+            }
+            switch.otherwise.collect(state, out);
+        }
+    }
+}
+
+impl<K: StateKey, C: Cost + Hash> SuccessorGenerator<WorldState<K>, StripsAction<K, C>, C>
+    for SuccessorTree<K, C>
+{
+    fn applicable(&self, state: &WorldState<K>, out: &mut Vec<StripsAction<K, C>>) {
+        let mut indices = Vec::new();
+        self.root.collect(state, &mut indices);
+        indices.sort_unstable();
+        out.extend(indices.into_iter().map(|index| self.actions[index].clone()));
+    }
+}
+
+impl<K: StateKey, C: fmt::Debug> fmt::Debug for SuccessorTree<K, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SuccessorTree")
+            .field("actions", &self.actions)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returns a sequence of actions produced by the generator to reach the goal while minimizing cost.
+///
+/// Returns `None` if no plan exists.
+pub fn plan_with_successors<S, A, P, G, C>(
+    initial_state: &S,
+    successors: &P,
+    goal: &G,
+) -> Option<(Vec<A>, C)>
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    P: SuccessorGenerator<S, A, C> + ?Sized,
+    G: Goal<S, C>,
+    C: Cost,
+{
+    match try_plan_with_successors(initial_state, successors, goal, &SearchBudget::unlimited()) {
+        Ok(plan) => {
+            let cost = plan.cost();
+            Some((plan.into_actions(), cost))
+        }
+        Err(PlanError::AlreadySatisfied) => Some((vec![], C::ZERO)),
+        Err(_) => None,
+    }
+}
+
+/// Returns a plan made of actions produced by the generator within the budget, or the reason none was found.
+pub fn try_plan_with_successors<S, A, P, G, C>(
+    initial_state: &S,
+    successors: &P,
+    goal: &G,
+    budget: &SearchBudget<C>,
+) -> Result<Plan<S, A, C>, PlanError<S>>
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    P: SuccessorGenerator<S, A, C> + ?Sized,
+    G: Goal<S, C>,
+    C: Cost,
+{
+    let mut initial_actions = Vec::new();
+    successors.applicable(initial_state, &mut initial_actions);
+    if let Some(error) = check_initial(initial_state, &initial_actions, goal) {
+        return Err(error);
+    }
+
+    let mut search = Search::new(initial_state, goal);
+    let outcome = search.run(successors, goal, budget);
+    search.result(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{plan, try_plan};
+
+    /// Actions over eight boolean facts and two integers, with overlapping preconditions.
+    fn actions() -> Vec<StripsAction> {
+        let mut actions = vec![StripsAction::new("reset").sets(8, 0).sets(9, 0)];
+        for fact in 0..8 {
+            actions.push(
+                StripsAction::new(format!("set {fact}"))
+                    .requires(fact, false)
+                    .adds(fact),
+            );
+            actions.push(
+                StripsAction::new(format!("clear {fact} after {}", fact + 1))
+                    .requires(fact, true)
+                    .requires((fact + 1) % 8, true)
+                    .deletes(fact)
+                    .with_cost(2),
+            );
+        }
+        for count in 0..3 {
+            actions.push(
+                StripsAction::new(format!("count {count}"))
+                    .requires(8, count)
+                    .requires(count as usize, true)
+                    .sets(8, count + 1),
+            );
+        }
+        actions.push(
+            StripsAction::new("finish")
+                .requires(8, 3)
+                .requires(9, 0)
+                .sets(9, 1)
+                .with_cost(3),
+        );
+        actions
+    }
+
+    #[test]
+    fn successor_tree_matches_scan() {
+        let actions = actions();
+        let tree = SuccessorTree::new(actions.clone());
+        assert_eq!(tree.actions(), actions.as_slice());
+
+        for bits in 0..256u32 {
+            for count in 0..4 {
+                let mut state = WorldState::new().with(8, count);
+                for fact in 0..8 {
+                    // Leave some facts unset, which rules out actions requiring any value
+                    if fact != bits as usize % 9 {
+                        state.set(fact, bits & (1 << fact) != 0);
+                    }
+                }
+                let mut scanned = Vec::new();
+                actions[..].applicable(&state, &mut scanned);
+                let mut generated = Vec::new();
+                tree.applicable(&state, &mut generated);
+                assert_eq!(generated, scanned);
+            }
+        }
+    }
+
+    #[test]
+    fn successor_tree_planning() {
+        let actions = actions();
+        let tree = SuccessorTree::new(actions.clone());
+        let mut state = WorldState::new().with(8, 0).with(9, 0);
+        for fact in 0..8 {
+            state.set(fact, false);
+        }
+        let goal = WorldState::new().with(9, 1).with(0, false);
+
+        let expected = plan(&state, &actions, &goal).unwrap();
+        assert_eq!(plan_with_successors(&state, &tree, &goal), Some(expected));
+
+        let budget = SearchBudget::unlimited().with_max_cost(5);
+        let error = try_plan_with_successors(&state, &tree, &goal, &budget).unwrap_err();
+        assert_eq!(
+            error,
+            try_plan(&state, &actions, &goal, &budget).unwrap_err()
+        );
+
+        let empty: SuccessorTree = SuccessorTree::new(vec![]);
+        let result = try_plan_with_successors(&state, &empty, &goal, &SearchBudget::unlimited());
+        assert_eq!(result.unwrap_err(), PlanError::NoApplicableActions);
+    }
+}
@@ -0,0 +1,272 @@
+use crate::{
+    check_initial, Action, Cost, Goal, Plan, PlanError, Search, SearchBudget, SearchStats,
+};
+use std::hash::Hash;
+use std::task::Poll;
+use std::time::{Duration, Instant};
+
+/// A search for a plan which can be run a little at a time.
+///
+/// Unlike `plan`, which blocks until the search is finished, a task is advanced by calling `step` or `step_for`,
+/// which expand a limited number of states or search for a limited time before returning.
+/// This allows long searches to be spread over multiple frames of a game loop.
+///
+/// The task owns its state, actions and goal, so it can be stored between frames.
+/// Its budget applies to the search as a whole, across all steps.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+/// use std::task::Poll;
+///
+/// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+/// struct Counter(u32);
+///
+/// #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+/// struct Increment;
+///
+/// impl Action<Counter> for Increment {
+///     fn is_applicable(&self, _state: &Counter) -> bool {
+///         true
+///     }
+///
+///     fn apply_mut(&self, state: &mut Counter) {
+///         state.0 += 1;
+///     }
+/// }
+///
+/// #[derive(PartialEq, Eq, Hash, Clone)]
+/// struct Reach(u32);
+///
+/// impl Goal<Counter> for Reach {
+///     fn is_satisfied(&self, state: &Counter) -> bool {
+///         state.0 == self.0
+///     }
+/// }
+///
+/// let mut task = PlannerTask::new(Counter(0), vec![Increment], Reach(10));
+///
+/// // Each frame expands up to 4 states
+/// let mut frames = 1;
+/// while task.step(4).is_pending() {
+///     frames += 1;
+/// }
+/// assert_eq!(frames, 3);
+///
+/// let Poll::Ready(Ok(plan)) = task.poll() else {
+///     panic!("expected a plan");
+/// };
+/// assert_eq!(plan.cost(), 10);
+/// assert_eq!(plan.into_actions(), vec![Increment; 10]);
+/// ```
+pub struct PlannerTask<S, A, G, C = i32>
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    G: Goal<S, C>,
+    C: Cost,
+{
+    actions: Vec<A>,
+    goal: G,
+    budget: SearchBudget<C>,
+    search: Option<Search<S, A, C>>,
+    outcome: Option<Result<Plan<S, A, C>, PlanError<S>>>,
+    expanded: usize,
+}
+
+impl<S, A, G, C> PlannerTask<S, A, G, C>
+where
+    S: Clone + Hash + Eq,
+    A: Action<S, C> + Eq + Clone + Hash,
+    G: Goal<S, C>,
+    C: Cost,
+{
+    /// Creates a task to plan from the given state to the goal, without a budget.
+    pub fn new(initial_state: S, actions: Vec<A>, goal: G) -> Self {
+        Self::with_budget(initial_state, actions, goal, SearchBudget::unlimited())
+    }
+
+    /// Creates a task to plan from the given state to the goal, limited by the given budget.
+    ///
+    /// A deadline in the budget is an absolute point in time, so it also counts the time between steps.
+    pub fn with_budget(
+        initial_state: S,
+        actions: Vec<A>,
+        goal: G,
+        budget: SearchBudget<C>,
+    ) -> Self {
+        let outcome = check_initial(&initial_state, &actions, &goal).map(Err);
+        let search = match outcome {
+            Some(_) => None,
+            None => Some(Search::new(&initial_state, &goal)),
+        };
+        Self {
+            actions,
+            goal,
+            budget,
+            search,
+            outcome,
+            expanded: 0,
+        }
+    }
+
+    /// Returns the goal this task is planning for.
+    pub fn goal(&self) -> &G {
+        &self.goal
+    }
+
+    /// Returns the total number of states expanded so far.
+    pub fn expanded(&self) -> usize {
+        self.search
+            .as_ref()
+            .map_or(self.expanded, |search| search.expanded())
+    }
+
+    /// Returns diagnostics about the search, if it is still running.
+    pub fn stats(&self) -> Option<SearchStats<S>> {
+        self.search.as_ref().map(|search| search.stats())
+    }
+
+    /// Returns true if the search has finished, successfully or not.
+    pub fn is_finished(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    /// Returns the result of the search if it has finished, without advancing it.
+    pub fn poll(&self) -> Poll<Result<Plan<S, A, C>, PlanError<S>>> {
+        match &self.outcome {
+            Some(outcome) => Poll::Ready(outcome.clone()),
+            None => Poll::Pending,
+        }
+    }
+
+    /// Advances the search by expanding at most the given number of states.
+    ///
+    /// Returns the result once the search has finished, and `Poll::Pending` otherwise.
+    pub fn step(&mut self, max_expanded: usize) -> Poll<Result<Plan<S, A, C>, PlanError<S>>> {
+        self.advance(|expanded| expanded < max_expanded)
+    }
+
+    /// Advances the search until the given amount of time has passed.
+    ///
+    /// Returns the result once the search has finished, and `Poll::Pending` otherwise.
+    pub fn step_for(&mut self, duration: Duration) -> Poll<Result<Plan<S, A, C>, PlanError<S>>> {
+        let deadline = Instant::now() + duration;
+        self.advance(|_| Instant::now() < deadline)
+    }
+
+    /// Runs the search until it finishes, and returns the result.
+    pub fn run(mut self) -> Result<Plan<S, A, C>, PlanError<S>> {
+        loop {
+            if let Poll::Ready(result) = self.step(usize::MAX) {
+                return result;
+            }
+        }
+    }
+
+    /// Stops the search and frees its memory.
+    ///
+    /// If the search has not finished, its result becomes `PlanError::Cancelled`.
+    pub fn cancel(&mut self) {
+        if let Some(search) = self.search.take() {
+            self.expanded = search.expanded();
+            self.outcome = Some(Err(PlanError::Cancelled));
+        }
+    }
+
+    /// Steps the search while the given condition holds for the number of states expanded by this call.
+    fn advance(
+        &mut self,
+        mut condition: impl FnMut(usize) -> bool,
+    ) -> Poll<Result<Plan<S, A, C>, PlanError<S>>> {
+        if let Some(search) = &mut self.search {
+            let start = search.expanded();
+            while condition(search.expanded() - start) {
+                if let Some(outcome) = search.step(&self.actions[..], &self.goal, &self.budget) {
+                    self.outcome = Some(search.result(outcome));
+                    self.expanded = search.expanded();
+                    self.search = None;
+                    break;
+                }
+            }
+        }
+        self.poll()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    struct Counter(u32);
+
+    #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    struct Increment;
+
+    impl Action<Counter> for Increment {
+        fn is_applicable(&self, _state: &Counter) -> bool {
+            true
+        }
+
+        fn apply_mut(&self, state: &mut Counter) {
+            state.0 += 1;
+        }
+    }
+
+    #[derive(PartialEq, Eq, Hash, Clone)]
+    struct Reach(u32);
+
+    impl Goal<Counter> for Reach {
+        fn is_satisfied(&self, state: &Counter) -> bool {
+            state.0 == self.0
+        }
+    }
+
+    #[test]
+    fn task_steps() {
+        let expected = Plan::from_actions(&Counter(0), vec![Increment; 3]);
+        let mut task = PlannerTask::new(Counter(0), vec![Increment], Reach(3));
+        assert_eq!(task.step(2), Poll::Pending);
+        assert_eq!(task.expanded(), 2);
+        assert_eq!(task.stats().unwrap().deepest_state, Counter(2));
+        assert_eq!(task.step(2), Poll::Ready(Ok(expected.clone())));
+        assert_eq!(task.expanded(), 3);
+        assert!(task.is_finished());
+
+        // Finished tasks keep their result
+        assert_eq!(task.step(2), Poll::Ready(Ok(expected.clone())));
+        task.cancel();
+        assert_eq!(task.poll(), Poll::Ready(Ok(expected.clone())));
+
+        let task = PlannerTask::new(Counter(3), vec![Increment], Reach(3));
+        assert_eq!(task.poll(), Poll::Ready(Err(PlanError::AlreadySatisfied)));
+    }
+
+    #[test]
+    fn task_cancel() {
+        let mut task = PlannerTask::new(Counter(0), vec![Increment], Reach(100));
+        assert_eq!(task.step(10), Poll::Pending);
+        task.cancel();
+        assert_eq!(task.poll(), Poll::Ready(Err(PlanError::Cancelled)));
+        assert_eq!(task.step(10), Poll::Ready(Err(PlanError::Cancelled)));
+        assert_eq!(task.expanded(), 10);
+    }
+
+    #[test]
+    fn task_budget() {
+        let budget = SearchBudget::unlimited().with_max_expanded(5);
+        let mut task = PlannerTask::with_budget(Counter(0), vec![Increment], Reach(100), budget);
+        assert_eq!(task.step(3), Poll::Pending);
+        assert!(matches!(
+            task.step(3),
+            Poll::Ready(Err(PlanError::BudgetExhausted(_)))
+        ));
+        assert_eq!(task.expanded(), 5);
+
+        let task = PlannerTask::new(Counter(0), vec![Increment], Reach(5));
+        let plan = task.run().unwrap();
+        assert_eq!(plan.cost(), 5);
+        assert_eq!(plan.into_actions(), vec![Increment; 5]);
+    }
+}
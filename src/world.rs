@@ -0,0 +1,541 @@
+use crate::{Cost, Goal};
+use std::fmt;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+/// A key identifying a fact in a `WorldState`.
+///
+/// Keys are usually a fieldless enum, mapped to consecutive indices starting from 0.
+/// `usize` implements this trait, for keys which are only known at runtime.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+///
+/// #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// enum Fact {
+///     HasAxe,
+///     Wood,
+/// }
+///
+/// impl StateKey for Fact {
+///     fn index(self) -> usize {
+///         self as usize
+///     }
+///
+///     fn from_index(index: usize) -> Self {
+///         [Fact::HasAxe, Fact::Wood][index]
+///     }
+/// }
+/// ```
+pub trait StateKey: Copy + Eq + Hash + fmt::Debug {
+    /// Returns the index of the key. Indices should be small, as states store booleans in bitsets.
+    fn index(self) -> usize;
+
+    /// Returns the key with the given index.
+    fn from_index(index: usize) -> Self;
+}
+
+impl StateKey for usize {
+    fn index(self) -> usize {
+        self
+    }
+
+    fn from_index(index: usize) -> Self {
+        index
+    }
+}
+
+/// The value of a fact in a `WorldState`.
+///
+/// Fieldless enums are stored as integers, converting them with `as i64`,
+/// and compared by converting the expected variant the same way.
+/// With the `serde` feature, values are written as plain booleans and integers.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+///
+/// #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// enum Weather {
+///     Sunny,
+///     Raining,
+/// }
+///
+/// let state: WorldState = WorldState::new().with(0, Weather::Raining as i64);
+/// assert_eq!(state.get_int(0), Some(Weather::Raining as i64));
+/// assert_ne!(state.get(0), Some(FactValue::from(Weather::Sunny as i64)));
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FactValue {
+    Bool(bool),
+    Int(i64),
+}
+
+impl From<bool> for FactValue {
+    fn from(value: bool) -> Self {
+        FactValue::Bool(value)
+    }
+}
+
+impl From<i64> for FactValue {
+    fn from(value: i64) -> Self {
+        FactValue::Int(value)
+    }
+}
+
+impl From<i32> for FactValue {
+    fn from(value: i32) -> Self {
+        FactValue::Int(value.into())
+    }
+}
+
+/// A state made of facts, each of which is a boolean or integer value for a key.
+///
+/// Keys which are not set have no value, which means "don't care" when the state is used as a goal or condition.
+/// Booleans are stored in bitsets, so states with many boolean facts are cheap to clone, hash and compare.
+/// With the `serde` feature, states are written as a map from each key which is set to its value.
+///
+/// A world state can be used as the state type for `plan` and `Agent` without a custom struct.
+/// It is also a goal, which is satisfied by any state with the same values for every key it sets.
+///
+/// # Example
+/// ```
+/// # use planning::*;
+///
+/// #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// enum Fact {
+///     HasAxe,
+///     Wood,
+/// }
+///
+/// impl StateKey for Fact {
+///     fn index(self) -> usize {
+///         self as usize
+///     }
+///
+///     fn from_index(index: usize) -> Self {
+///         [Fact::HasAxe, Fact::Wood][index]
+///     }
+/// }
+///
+/// #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// enum Camp {
+///     GetAxe,
+///     ChopWood,
+/// }
+///
+/// impl Action<WorldState<Fact>> for Camp {
+///     fn is_applicable(&self, state: &WorldState<Fact>) -> bool {
+///         match self {
+///             Camp::GetAxe => state.get_bool(Fact::HasAxe) == Some(false),
+///             Camp::ChopWood => state.get_bool(Fact::HasAxe) == Some(true),
+///         }
+///     }
+///
+///     fn apply_mut(&self, state: &mut WorldState<Fact>) {
+///         match self {
+///             Camp::GetAxe => state.set(Fact::HasAxe, true),
+///             Camp::ChopWood => state.set(Fact::Wood, state.get_int(Fact::Wood).unwrap_or(0) + 1),
+///         }
+///     }
+/// }
+///
+/// let state = WorldState::new().with(Fact::HasAxe, false).with(Fact::Wood, 0);
+/// let goal = WorldState::new().with(Fact::Wood, 2);
+///
+/// let (path, _) = plan(&state, &[Camp::GetAxe, Camp::ChopWood], &goal).unwrap();
+/// assert_eq!(path, vec![Camp::GetAxe, Camp::ChopWood, Camp::ChopWood]);
+///
+/// let end = Plan::from_actions(&state, path).final_state().unwrap().clone();
+/// assert!(end.matches(&goal));
+/// assert_eq!(
+///     state.diff(&end),
+///     vec![
+///         (Fact::HasAxe, Some(FactValue::Bool(false)), Some(FactValue::Bool(true))),
+///         (Fact::Wood, Some(FactValue::Int(0)), Some(FactValue::Int(2))),
+///     ]
+/// );
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct WorldState<K = usize> {
+    /// A bit for each boolean fact which is set.
+    known: Vec<u64>,
+    /// A bit for each boolean fact which is true.
+    bools: Vec<u64>,
+    /// Integer facts, sorted by key index.
+    ints: Vec<(u32, i64)>,
+    _key: PhantomData<fn() -> K>,
+}
+
+/// Returns the word and bit of a boolean fact in the bitsets.
+fn bit(index: usize) -> (usize, u64) {
+    (index / 64, 1 << (index % 64))
+}
+
+impl<K> Default for WorldState<K> {
+    fn default() -> Self {
+        Self {
+            known: Vec::new(),
+            bools: Vec::new(),
+            ints: Vec::new(),
+            _key: PhantomData,
+        }
+    }
+}
+
+impl<K: StateKey> WorldState<K> {
+    /// Returns a state without any facts.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the value of a fact and returns the state.
+    pub fn with(mut self, key: K, value: impl Into<FactValue>) -> Self {
+        self.set(key, value);
+        self
+    }
+
+    /// Sets the value of a fact, replacing any previous value.
+    pub fn set(&mut self, key: K, value: impl Into<FactValue>) {
+        let index = key.index();
+        match value.into() {
+            FactValue::Bool(value) => {
+                self.remove_int(index);
+                let (word, bit) = bit(index);
+                if self.known.len() <= word {
+                    self.known.resize(word + 1, 0);
+                    self.bools.resize(word + 1, 0);
+                }
+                self.known[word] |= bit;
+                if value {
+                    self.bools[word] |= bit;
+                } else {
+                    self.bools[word] &= !bit;
+                }
+            }
+            FactValue::Int(value) => {
+                self.remove_bool(index);
+                let key = index as u32;
+                match self.ints.binary_search_by_key(&key, |&(k, _)| k) {
+                    Ok(position) => self.ints[position].1 = value,
+                    Err(position) => self.ints.insert(position, (key, value)),
+                }
+            }
+        }
+    }
+
+    /// Removes the value of a fact, so that it doesn't matter.
+    pub fn unset(&mut self, key: K) {
+        let index = key.index();
+        self.remove_bool(index);
+        self.remove_int(index);
+    }
+
+    /// Returns the value of a fact, if it is set.
+    pub fn get(&self, key: K) -> Option<FactValue> {
+        let index = key.index();
+        let (word, bit) = bit(index);
+        if self.known.get(word).is_some_and(|known| known & bit != 0) {
+            return Some(FactValue::Bool(self.bools[word] & bit != 0));
+        }
+        self.int_position(index)
+            .map(|position| FactValue::Int(self.ints[position].1))
+    }
+
+    /// Returns the value of a fact, if it is set to a boolean.
+    pub fn get_bool(&self, key: K) -> Option<bool> {
+        match self.get(key) {
+            Some(FactValue::Bool(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a fact, if it is set to an integer.
+    pub fn get_int(&self, key: K) -> Option<i64> {
+        match self.get(key) {
+            Some(FactValue::Int(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the fact has a value.
+    pub fn is_set(&self, key: K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns the number of facts which are set.
+    pub fn len(&self) -> usize {
+        let bools: u32 = self.known.iter().map(|word| word.count_ones()).sum();
+        bools as usize + self.ints.len()
+    }
+
+    /// Returns true if no facts are set.
+    pub fn is_empty(&self) -> bool {
+        self.known.is_empty() && self.ints.is_empty()
+    }
+
+    /// Returns every fact which is set, in order of key index.
+    pub fn facts(&self) -> Vec<(K, FactValue)> {
+        let mut facts: Vec<(usize, FactValue)> = self.bool_indices().collect();
+        facts.extend(
+            self.ints
+                .iter()
+                .map(|&(index, value)| (index as usize, FactValue::Int(value))),
+        );
+        facts.sort_unstable_by_key(|&(index, _)| index);
+        facts
+            .into_iter()
+            .map(|(index, value)| (K::from_index(index), value))
+            .collect()
+    }
+
+    /// Returns true if this state has the same value for every fact set in the partial state.
+    ///
+    /// Facts which are not set in the partial state don't matter.
+    pub fn matches(&self, partial: &WorldState<K>) -> bool {
+        let bools_match = partial.known.iter().enumerate().all(|(word, &required)| {
+            let known = self.known.get(word).copied().unwrap_or(0);
+            let differs = self.bools.get(word).copied().unwrap_or(0) ^ partial.bools[word];
+            required & !known == 0 && required & differs == 0
+        });
+        bools_match
+            && partial
+                .ints
+                .iter()
+                .all(|&(index, value)| self.int_value(index) == Some(value))
+    }
+
+    /// Returns true if any fact is set in both states with different values.
+    pub fn conflicts_with(&self, other: &WorldState<K>) -> bool {
+        let bools_conflict = self
+            .known
+            .iter()
+            .zip(&other.known)
+            .zip(self.bools.iter().zip(&other.bools))
+            .any(|((a, b), (x, y))| a & b & (x ^ y) != 0);
+        let bool_int_conflict = self
+            .ints
+            .iter()
+            .any(|&(index, _)| other.has_bool(index as usize))
+            || other
+                .ints
+                .iter()
+                .any(|&(index, _)| self.has_bool(index as usize));
+        bools_conflict
+            || bool_int_conflict
+            || self.ints.iter().any(|&(index, value)| {
+                other
+                    .int_value(index)
+                    .is_some_and(|other_value| other_value != value)
+            })
+    }
+
+    /// Returns the facts of the partial state which this state doesn't match.
+    pub fn unmet(&self, partial: &WorldState<K>) -> WorldState<K> {
+        let mut unmet = WorldState::new();
+        for (key, value) in partial.facts() {
+            if self.get(key) != Some(value) {
+                unmet.set(key, value);
+            }
+        }
+        unmet
+    }
+
+    /// Sets every fact which is set in the other state, keeping the values of the rest.
+    pub fn merge(&mut self, other: &WorldState<K>) {
+        for (key, value) in other.facts() {
+            self.set(key, value);
+        }
+    }
+
+    /// Returns every fact with a different value in the other state,
+    /// as the key, the value in this state, and the value in the other state, in order of key index.
+    pub fn diff(&self, other: &WorldState<K>) -> Vec<(K, Option<FactValue>, Option<FactValue>)> {
+        let mut keys: Vec<usize> = self
+            .facts()
+            .into_iter()
+            .chain(other.facts())
+            .map(|(key, _)| key.index())
+            .collect();
+        keys.sort_unstable();
+        keys.dedup();
+        keys.into_iter()
+            .map(K::from_index)
+            .filter_map(|key| {
+                let (before, after) = (self.get(key), other.get(key));
+                (before != after).then_some((key, before, after))
+            })
+            .collect()
+    }
+
+    /// Returns the indices and values of every boolean fact which is set.
+    fn bool_indices(&self) -> impl Iterator<Item = (usize, FactValue)> + '_ {
+        self.known
+            .iter()
+            .enumerate()
+            .flat_map(move |(word, &known)| {
+                (0..64)
+                    .filter(move |bit| known & (1 << bit) != 0)
+                    .map(move |bit| {
+                        let value = self.bools[word] & (1 << bit) != 0;
+                        (word * 64 + bit, FactValue::Bool(value))
+                    })
+            })
+    }
+
+    fn has_bool(&self, index: usize) -> bool {
+        let (word, bit) = bit(index);
+        self.known.get(word).is_some_and(|known| known & bit != 0)
+    }
+
+    fn int_position(&self, index: usize) -> Option<usize> {
+        self.ints
+            .binary_search_by_key(&(index as u32), |&(k, _)| k)
+            .ok()
+    }
+
+    fn int_value(&self, index: u32) -> Option<i64> {
+        self.int_position(index as usize)
+            .map(|position| self.ints[position].1)
+    }
+
+    fn remove_bool(&mut self, index: usize) {
+        let (word, bit) = bit(index);
+        if word < self.known.len() {
+            self.known[word] &= !bit;
+            self.bools[word] &= !bit;
+            // Trailing empty words are removed, so equal states have equal bitsets
+            while self.known.last() == Some(&0) {
+                self.known.pop();
+                self.bools.pop();
+            }
+        }
+    }
+
+    fn remove_int(&mut self, index: usize) {
+        if let Some(position) = self.int_position(index) {
+            self.ints.remove(position);
+        }
+    }
+}
+
+impl<K: StateKey> fmt::Debug for WorldState<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.facts()).finish()
+    }
+}
+
+impl<K: StateKey, C: Cost> Goal<WorldState<K>, C> for WorldState<K> {
+    fn is_satisfied(&self, state: &WorldState<K>) -> bool {
+        state.matches(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<K: StateKey + serde::Serialize> serde::Serialize for WorldState<K> {
+    fn serialize<Ser: serde::Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        serializer.collect_map(self.facts())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, K: StateKey + serde::Deserialize<'de>> serde::Deserialize<'de> for WorldState<K> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Facts are set one at a time, so the bitsets are the same as for a state built in code
+        let facts = std::collections::HashMap::<K, FactValue>::deserialize(deserializer)?;
+        Ok(facts
+            .into_iter()
+            .fold(WorldState::new(), |state, (key, value)| {
+                state.with(key, value)
+            }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn world_state_facts() {
+        let mut state: WorldState = WorldState::new().with(3, true).with(70, false).with(5, 7);
+        assert_eq!(state.len(), 3);
+        assert_eq!(state.get(3), Some(FactValue::Bool(true)));
+        assert_eq!(state.get_bool(70), Some(false));
+        assert_eq!(state.get_int(5), Some(7));
+        assert_eq!(state.get(4), None);
+        assert_eq!(
+            state.facts(),
+            vec![
+                (3, FactValue::Bool(true)),
+                (5, FactValue::Int(7)),
+                (70, FactValue::Bool(false)),
+            ]
+        );
+
+        // Changing the type of a fact replaces it
+        state.set(5, false);
+        state.set(3, 1);
+        assert_eq!(state.get(5), Some(FactValue::Bool(false)));
+        assert_eq!(state.get(3), Some(FactValue::Int(1)));
+        assert_eq!(state.len(), 3);
+
+        // States with the same facts are equal, however they were built
+        state.unset(70);
+        state.unset(5);
+        assert_eq!(state, WorldState::new().with(3, 1));
+        assert_eq!(format!("{state:?}"), "{3: Int(1)}");
+    }
+
+    #[test]
+    fn world_state_matching() {
+        let state: WorldState = WorldState::new().with(0, true).with(1, false).with(2, 5);
+        assert!(state.matches(&WorldState::new()));
+        assert!(state.matches(&WorldState::new().with(0, true).with(2, 5)));
+        assert!(!state.matches(&WorldState::new().with(1, true)));
+        assert!(!state.matches(&WorldState::new().with(2, 6)));
+        assert!(!state.matches(&WorldState::new().with(100, false)));
+        assert!(!state.matches(&WorldState::new().with(0, 1)));
+
+        let other = WorldState::new().with(1, false).with(3, true);
+        assert!(!state.conflicts_with(&other));
+        assert!(state.conflicts_with(&other.clone().with(2, 4)));
+        assert!(state.conflicts_with(&other.clone().with(0, false)));
+        assert!(state.conflicts_with(&other.clone().with(2, true)));
+
+        let goal = WorldState::new().with(0, true).with(1, true).with(2, 6);
+        assert_eq!(
+            state.unmet(&goal),
+            WorldState::new().with(1, true).with(2, 6)
+        );
+
+        let mut merged = state.clone();
+        merged.merge(&goal);
+        assert!(merged.matches(&goal));
+        assert_eq!(
+            state.diff(&merged),
+            vec![
+                (1, Some(FactValue::Bool(false)), Some(FactValue::Bool(true))),
+                (2, Some(FactValue::Int(5)), Some(FactValue::Int(6))),
+            ]
+        );
+        assert!(Goal::<_, i32>::is_satisfied(&goal, &merged));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn world_state_serde() {
+        let state: WorldState = WorldState::new().with(70, true).with(3, false).with(5, -2);
+        let json = serde_json::to_string(&state).unwrap();
+        assert_eq!(json, r#"{"3":false,"5":-2,"70":true}"#);
+        assert_eq!(serde_json::from_str::<WorldState>(&json).unwrap(), state);
+
+        // States are rebuilt from their facts, so they equal states built in code
+        let state: WorldState = serde_json::from_str(r#"{"1":true,"130":4}"#).unwrap();
+        assert_eq!(state, WorldState::new().with(1, true).with(130, 4));
+        assert!(state.matches(&WorldState::new().with(1, true)));
+        assert!(serde_json::from_str::<WorldState>(r#"{"1":"yes"}"#).is_err());
+    }
+}
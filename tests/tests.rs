@@ -118,3 +118,130 @@ fn plan_complex() {
     );
     assert_eq!(cost, 11);
 }
+
+#[test]
+fn agent_try_plan_all() {
+    #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    struct Counter(u32);
+
+    #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    struct Increment;
+
+    impl Action<Counter> for Increment {
+        fn is_applicable(&self, state: &Counter) -> bool {
+            state.0 < 10
+        }
+
+        fn apply_mut(&self, state: &mut Counter) {
+            state.0 += 1;
+        }
+    }
+
+    #[derive(PartialEq, Eq, Hash, Clone, Debug)]
+    struct Reach(u32);
+
+    impl Goal<Counter> for Reach {
+        fn is_satisfied(&self, state: &Counter) -> bool {
+            state.0 == self.0
+        }
+
+        fn priority(&self, _state: &Counter) -> i32 {
+            2 * self.0 as i32
+        }
+    }
+
+    let agent = Agent::new(
+        Counter(0),
+        vec![Increment],
+        vec![Reach(0), Reach(3), Reach(20), Reach(5)],
+    );
+
+    let results = agent.try_plan_all(&SearchBudget::unlimited());
+    let goals: Vec<_> = results.iter().map(|(goal, _)| goal.0).collect();
+    assert_eq!(goals, vec![20, 5, 3, 0]);
+    assert!(matches!(results[0].1, Err(PlanError::Exhausted(_))));
+    assert_eq!(
+        results[1].1,
+        Ok(Plan::from_actions(&Counter(0), vec![Increment; 5]))
+    );
+    assert_eq!(
+        results[2].1,
+        Ok(Plan::from_actions(&Counter(0), vec![Increment; 3]))
+    );
+    assert_eq!(results[3].1, Err(PlanError::AlreadySatisfied));
+
+    // The first search uses up the shared budget
+    let results = agent.try_plan_all(&SearchBudget::unlimited().with_max_expanded(11));
+    assert!(matches!(results[0].1, Err(PlanError::Exhausted(_))));
+    assert!(matches!(results[1].1, Err(PlanError::BudgetExhausted(_))));
+
+    let result = agent.try_plan_constant(&SearchBudget::unlimited().with_max_expanded(11));
+    assert!(matches!(result, Err(PlanError::BudgetExhausted(_))));
+
+    let (goal, plan) = agent.try_plan_profit(&SearchBudget::unlimited()).unwrap();
+    assert_eq!(goal, &Reach(5));
+    assert_eq!(plan.cost(), 5);
+    assert_eq!(plan.into_actions(), vec![Increment; 5]);
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn agent_derive() {
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    struct State {
+        num_flowers: u16,
+        hungry: bool,
+        picnic_set: bool,
+    }
+
+    #[derive(Action, Clone, Debug, PartialEq, Eq, Hash)]
+    #[action(state = State, cost = u32)]
+    enum MyAction {
+        #[action(requires = state.num_flowers < 5, effect = state.num_flowers += 1)]
+        PickFlower,
+        #[action(requires = !state.picnic_set, effect = state.picnic_set = true, cost = 2)]
+        SetPicnic,
+        #[action(
+            requires = state.hungry && state.picnic_set,
+            effect = {
+                state.hungry = false;
+                state.picnic_set = false;
+            },
+        )]
+        Eat,
+    }
+
+    #[derive(Goal, Clone, Debug, PartialEq, Eq, Hash)]
+    #[goal(state = State, cost = u32)]
+    enum MyGoal {
+        #[goal(
+            satisfied = state.num_flowers >= *_0,
+            priority = 1,
+            heuristic = u32::from(_0.saturating_sub(state.num_flowers)),
+        )]
+        Bouquet(u16),
+        #[goal(satisfied = !state.hungry, priority = if state.hungry { 2 } else { 0 })]
+        Eaten,
+    }
+
+    let mut agent = Agent::new(
+        State {
+            num_flowers: 0,
+            hungry: true,
+            picnic_set: false,
+        },
+        vec![MyAction::PickFlower, MyAction::SetPicnic, MyAction::Eat],
+        vec![MyGoal::Bouquet(5), MyGoal::Eaten],
+    );
+
+    let (goal, plan, cost) = agent.plan_dynamic::<u32>().unwrap();
+    assert_eq!(goal, &MyGoal::Eaten);
+    assert_eq!(plan, vec![MyAction::SetPicnic, MyAction::Eat]);
+    assert_eq!(cost, 3);
+
+    agent.state.hungry = false;
+    let (goal, plan, cost) = agent.plan_dynamic::<u32>().unwrap();
+    assert_eq!(goal, &MyGoal::Bouquet(5));
+    assert_eq!(plan, vec![MyAction::PickFlower; 5]);
+    assert_eq!(cost, 5);
+}